@@ -1,21 +1,45 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Timestamp,
-    Uint128,
+    coin, to_binary, Addr, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Empty, Env, Event,
+    MessageInfo, Order, Response, StdError, StdResult, Storage, Timestamp, Uint128,
 };
 use cw2::set_contract_version;
-use cw_utils::NativeBalance;
+use cw_storage_plus::Bound;
+use cw_utils::{one_coin, NativeBalance};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::events::*;
+use crate::msg::{
+    BalanceResponse, ExecuteMsg, FulfillmentStats, FundingResponse, FundingStatus, InstantiateMsg,
+    OfferResponse, ProjectBalanceResponse, ProjectResponse, QueryMsg, ReferralStatsResponse,
+    ReputationResponse, UserBalanceResponse,
+};
 use crate::state::*;
-use crate::utils::generate_id;
+use crate::utils::{generate_id, hash_code, telemetry_digest};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:neariot-cosmwasm";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// pagination for list queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// shipped purchases the buyer doesn't confirm within 30 days count as delivered
+const AUTO_CONFIRM_PERIOD: u64 = 30 * 24 * 60 * 60;
+
+// metadata and campaign settings are locked once funding closes
+const EDITABLE: &[ProjectStatus] = &[ProjectStatus::Draft, ProjectStatus::Live];
+// funded and not yet completed: shipping and milestones
+const FULFILLING: &[ProjectStatus] = &[ProjectStatus::Funded, ProjectStatus::Delivering];
+const OPEN: &[ProjectStatus] = &[
+    ProjectStatus::Draft,
+    ProjectStatus::Live,
+    ProjectStatus::Funded,
+    ProjectStatus::Delivering,
+];
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     _deps: DepsMut,
@@ -36,6 +60,34 @@ pub fn execute(
     _info: MessageInfo,
     _msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    match &_msg {
+        // admin must always be able to lift a pause, backers to get refunds
+        ExecuteMsg::Pause { .. }
+        | ExecuteMsg::Unpause { .. }
+        | ExecuteMsg::ModerateProject { .. }
+        | ExecuteMsg::UpdateAccessList { .. }
+        | ExecuteMsg::SetVerifiedCreatorsOnly { .. }
+        | ExecuteMsg::AddVerifier { .. }
+        | ExecuteMsg::AddOracle { .. }
+        | ExecuteMsg::RemoveOracle { .. }
+        | ExecuteMsg::RemoveVerifier { .. }
+        | ExecuteMsg::AddArbitrator { .. }
+        | ExecuteMsg::RemoveArbitrator { .. }
+        | ExecuteMsg::ResolveDispute { .. }
+        | ExecuteMsg::ClaimRefund {} => {}
+        ExecuteMsg::CreateProject { .. } | ExecuteMsg::LaunchProject { .. } => {
+            assert_not_paused(_deps.storage, Some(PauseOperation::ProjectCreation))?
+        }
+        ExecuteMsg::BuyOffer { .. } | ExecuteMsg::Pledge { .. } | ExecuteMsg::Subscribe { .. } => {
+            assert_not_paused(_deps.storage, Some(PauseOperation::Purchases))?
+        }
+        ExecuteMsg::Withdraw { .. }
+        | ExecuteMsg::ClaimSubscription { .. }
+        | ExecuteMsg::ClaimReferralRewards {} => {
+            assert_not_paused(_deps.storage, Some(PauseOperation::Withdrawals))?
+        }
+        _ => assert_not_paused(_deps.storage, None)?,
+    }
     match _msg {
         ExecuteMsg::RegisterUser {} => execute_register_user(_deps, _env, _info),
         ExecuteMsg::CreateProject { metadata } => {
@@ -44,9 +96,13 @@ pub fn execute(
         ExecuteMsg::UpdateProject { id, metadata } => {
             execute_update_project(_deps, _env, _info, id, metadata)
         }
+        ExecuteMsg::LaunchProject { id } => execute_launch_project(_deps, _env, _info, id),
         ExecuteMsg::UpdateTimestamp { id, timestamp } => {
             execute_update_timestamp(_deps, _env, _info, id, timestamp)
         }
+        ExecuteMsg::UpdateGoal { id, goal, denom } => {
+            execute_update_goal(_deps, _env, _info, id, goal, denom)
+        }
         ExecuteMsg::DeleteProject {} => todo!(),
         ExecuteMsg::CreateOffer {
             id,
@@ -63,6 +119,20 @@ pub fn execute(
         } => execute_update_project_offer(
             _deps, _env, _info, id, offer_id, min_price, metadata, expire_at,
         ),
+        ExecuteMsg::SetCollaborator { id, address, roles } => {
+            execute_set_collaborator(_deps, _env, _info, id, address, roles)
+        }
+        ExecuteMsg::ProposeOwnershipTransfer { id, new_owner } => {
+            execute_propose_ownership_transfer(_deps, _env, _info, id, new_owner)
+        }
+        ExecuteMsg::AcceptOwnershipTransfer { id } => {
+            execute_accept_ownership_transfer(_deps, _env, _info, id)
+        }
+        ExecuteMsg::UpdateOfferPricing {
+            id,
+            offer_id,
+            schedule,
+        } => execute_update_offer_pricing(_deps, _env, _info, id, offer_id, schedule),
         ExecuteMsg::DeleteOffer { id, offer_id } => {
             execute_delete_project_offer(_deps, _env, _info, id, offer_id)
         }
@@ -71,15 +141,192 @@ pub fn execute(
             offer_id,
             metadata,
             rate,
-        } => execute_buy_project_offer(_deps, _env, _info, project_id, offer_id, metadata, rate),
+            promo_code,
+            referrer,
+        } => execute_buy_project_offer(
+            _deps, _env, _info, project_id, offer_id, metadata, rate, promo_code, referrer,
+        ),
+        ExecuteMsg::AddPromoCode {
+            id,
+            code_hash,
+            discount,
+            max_uses,
+            expire_at,
+        } => execute_add_promo_code(
+            _deps, _env, _info, id, code_hash, discount, max_uses, expire_at,
+        ),
+        ExecuteMsg::RemovePromoCode { id, code_hash } => {
+            execute_remove_promo_code(_deps, _env, _info, id, code_hash)
+        }
         ExecuteMsg::RateOffer {
             project_id,
             offer_id,
             rate,
         } => execute_rate_project_offer(_deps, _env, _info, project_id, offer_id, rate),
+        ExecuteMsg::AddMilestone {
+            id,
+            metadata,
+            tranche,
+            condition,
+        } => execute_add_milestone(_deps, _env, _info, id, metadata, tranche, condition),
+        ExecuteMsg::MarkShipped {
+            project_id,
+            purchase_id,
+            tracking_hash,
+        } => execute_mark_shipped(_deps, _env, _info, project_id, purchase_id, tracking_hash),
+        ExecuteMsg::ConfirmDelivery {
+            project_id,
+            purchase_id,
+        } => execute_confirm_delivery(_deps, _env, _info, project_id, purchase_id),
+        ExecuteMsg::AttestMilestone {
+            project_id,
+            milestone_id,
+            met,
+            evidence_uri,
+        } => execute_attest_milestone(
+            _deps,
+            _env,
+            _info,
+            project_id,
+            milestone_id,
+            met,
+            evidence_uri,
+        ),
+        ExecuteMsg::AddStretchGoal {
+            id,
+            threshold,
+            offer_ids,
+            metadata,
+        } => execute_add_stretch_goal(_deps, _env, _info, id, threshold, offer_ids, metadata),
+        ExecuteMsg::RemoveStretchGoal { id, goal_id } => {
+            execute_remove_stretch_goal(_deps, _env, _info, id, goal_id)
+        }
+        ExecuteMsg::UpdateMinPledge { id, min_pledge } => {
+            execute_update_min_pledge(_deps, _env, _info, id, min_pledge)
+        }
+        ExecuteMsg::Pledge { project_id } => execute_pledge(_deps, _env, _info, project_id),
+        ExecuteMsg::UpdateCancellationPolicy { id, cutoff, fee } => {
+            execute_update_cancellation_policy(_deps, _env, _info, id, cutoff, fee)
+        }
+        ExecuteMsg::UpdateSubscriptionPlan {
+            id,
+            period,
+            min_price,
+        } => execute_update_subscription_plan(_deps, _env, _info, id, period, min_price),
+        ExecuteMsg::Subscribe {
+            project_id,
+            periods,
+        } => execute_subscribe(_deps, _env, _info, project_id, periods),
+        ExecuteMsg::ClaimSubscription {
+            project_id,
+            subscription_id,
+        } => execute_claim_subscription(_deps, _env, _info, project_id, subscription_id),
+        ExecuteMsg::CancelSubscription {
+            project_id,
+            subscription_id,
+        } => execute_cancel_subscription(_deps, _env, _info, project_id, subscription_id),
+        ExecuteMsg::CancelPurchase {
+            project_id,
+            purchase_id,
+            amount,
+        } => execute_cancel_purchase(_deps, _env, _info, project_id, purchase_id, amount),
         ExecuteMsg::WatchProject { id } => execute_watch_project(_deps, _env, _info, id),
         ExecuteMsg::UnwatchProject { id } => execute_unwatch_project(_deps, _env, _info, id),
         ExecuteMsg::RateProject { id, rate } => execute_rate_project(_deps, _env, _info, id, rate),
+        ExecuteMsg::Pause { operation } => execute_pause(_deps, _env, _info, operation),
+        ExecuteMsg::Unpause { operation } => execute_unpause(_deps, _env, _info, operation),
+        ExecuteMsg::ModerateProject { id, action, reason } => {
+            execute_moderate_project(_deps, _env, _info, id, action, reason)
+        }
+        ExecuteMsg::ClaimRefund {} => execute_claim_refund(_deps, _env, _info),
+        ExecuteMsg::Withdraw { project_id } => execute_withdraw(_deps, _env, _info, project_id),
+        ExecuteMsg::UpdateReferralShare { id, share } => {
+            execute_update_referral_share(_deps, _env, _info, id, share)
+        }
+        ExecuteMsg::ClaimReferralRewards {} => execute_claim_referral_rewards(_deps, _env, _info),
+        ExecuteMsg::RegisterDevice {
+            project_id,
+            device_id,
+            pubkey,
+            key_type,
+            model,
+        } => execute_register_device(
+            _deps, _env, _info, project_id, device_id, pubkey, key_type, model,
+        ),
+        ExecuteMsg::RotateDeviceKey {
+            project_id,
+            device_id,
+            pubkey,
+            key_type,
+        } => execute_rotate_device_key(_deps, _env, _info, project_id, device_id, pubkey, key_type),
+        ExecuteMsg::RetireDevice {
+            project_id,
+            device_id,
+        } => execute_retire_device(_deps, _env, _info, project_id, device_id),
+        ExecuteMsg::SubmitTelemetry {
+            project_id,
+            device_id,
+            payload_hash,
+            counter,
+            signature,
+        } => execute_submit_telemetry(
+            _deps,
+            _env,
+            _info,
+            project_id,
+            device_id,
+            payload_hash,
+            counter,
+            signature,
+        ),
+        ExecuteMsg::UpdateAccessList { list, add, remove } => {
+            execute_update_access_list(_deps, _env, _info, list, add, remove)
+        }
+        ExecuteMsg::SetVerifiedCreatorsOnly { enabled } => {
+            execute_set_verified_creators_only(_deps, _env, _info, enabled)
+        }
+        ExecuteMsg::AddVerifier { address } => execute_add_verifier(_deps, _env, _info, address),
+        ExecuteMsg::RemoveVerifier { address } => {
+            execute_remove_verifier(_deps, _env, _info, address)
+        }
+        ExecuteMsg::OpenDispute {
+            project_id,
+            purchase_id,
+            reason,
+            evidence_uri,
+        } => execute_open_dispute(
+            _deps,
+            _env,
+            _info,
+            project_id,
+            purchase_id,
+            reason,
+            evidence_uri,
+        ),
+        ExecuteMsg::ResolveDispute {
+            dispute_id,
+            resolution,
+        } => execute_resolve_dispute(_deps, _env, _info, dispute_id, resolution),
+        ExecuteMsg::AddArbitrator { address } => {
+            execute_add_arbitrator(_deps, _env, _info, address)
+        }
+        ExecuteMsg::RemoveArbitrator { address } => {
+            execute_remove_arbitrator(_deps, _env, _info, address)
+        }
+        ExecuteMsg::AddOracle {
+            address,
+            project_id,
+        } => execute_add_oracle(_deps, _env, _info, address, project_id),
+        ExecuteMsg::RemoveOracle {
+            address,
+            project_id,
+        } => execute_remove_oracle(_deps, _env, _info, address, project_id),
+        ExecuteMsg::GrantVerification { address, expire_at } => {
+            execute_grant_verification(_deps, _env, _info, address, expire_at)
+        }
+        ExecuteMsg::RevokeVerification { address } => {
+            execute_revoke_verification(_deps, _env, _info, address)
+        }
     }
 }
 
@@ -88,22 +335,115 @@ pub fn query(_deps: Deps, _env: Env, _msg: QueryMsg) -> StdResult<Binary> {
     match _msg {
         QueryMsg::GetUser { id } => to_binary(&query_get_user(_deps, id)?),
         QueryMsg::ListUser {} => to_binary(&query_list_user(_deps)?),
-        QueryMsg::GetProject { id } => to_binary(&query_get_project(_deps, id)?),
-        QueryMsg::ListProject {} => to_binary(&query_list_project(_deps)?),
+        QueryMsg::GetProject { id } => to_binary(&query_get_project(_deps, _env, id)?),
+        QueryMsg::ListProject { status } => to_binary(&query_list_project(_deps, _env, status)?),
         QueryMsg::GetProjectOffers { project_id } => {
-            to_binary(&query_get_project_offers(_deps, project_id)?)
+            to_binary(&query_get_project_offers(_deps, _env, project_id)?)
         }
         QueryMsg::GetProjectOffer {
             project_id,
             offer_id,
-        } => to_binary(&query_get_project_offer(_deps, project_id, offer_id)?),
-        QueryMsg::GetFunding {} => todo!(),
-        QueryMsg::ListFunding {} => todo!(),
+        } => to_binary(&query_get_project_offer(_deps, _env, project_id, offer_id)?),
+        QueryMsg::ListPromoCodes { project_id } => {
+            to_binary(&query_list_promo_codes(_deps, project_id)?)
+        }
+        QueryMsg::GetMilestones { project_id } => {
+            to_binary(&query_get_milestones(_deps, project_id)?)
+        }
+        QueryMsg::GetStretchGoals { project_id } => {
+            to_binary(&query_get_stretch_goals(_deps, project_id)?)
+        }
+        QueryMsg::GetReputation { owner } => to_binary(&query_get_reputation(_deps, _env, owner)?),
+        QueryMsg::GetFunding { project_id } => {
+            to_binary(&query_get_funding(_deps, _env, project_id)?)
+        }
+        QueryMsg::ListFunding {
+            status,
+            start_after,
+            limit,
+        } => to_binary(&query_list_funding(
+            _deps,
+            _env,
+            status,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ListProjectContributions {
+            project_id,
+            start_after,
+            limit,
+        } => to_binary(&query_list_project_contributions(
+            _deps,
+            project_id,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ListBackerContributions {
+            backer,
+            start_after,
+            limit,
+        } => to_binary(&query_list_backer_contributions(
+            _deps,
+            backer,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ListSubscriptions {
+            project_id,
+            start_after,
+            limit,
+        } => to_binary(&query_list_subscriptions(
+            _deps,
+            project_id,
+            start_after,
+            limit,
+        )?),
         QueryMsg::GetWatching {} => todo!(),
         QueryMsg::GetRating {} => todo!(),
         QueryMsg::ListRating {} => todo!(),
-        QueryMsg::GetBalance {} => todo!(),
+        QueryMsg::GetBalance {} => to_binary(&query_get_balance(_deps, _env)?),
+        QueryMsg::GetProjectBalance { project_id } => {
+            to_binary(&query_get_project_balance(_deps, _env, project_id)?)
+        }
+        QueryMsg::GetUserBalance { address } => to_binary(&query_get_user_balance(_deps, address)?),
+        QueryMsg::GetDevice {
+            project_id,
+            device_id,
+        } => to_binary(&query_get_device(_deps, project_id, device_id)?),
+        QueryMsg::ListDevices {
+            project_id,
+            start_after,
+            limit,
+        } => to_binary(&query_list_devices(_deps, project_id, start_after, limit)?),
+        QueryMsg::GetAttestation {
+            project_id,
+            device_id,
+        } => to_binary(&query_get_attestation(_deps, project_id, device_id)?),
+        QueryMsg::GetReferralStats { address } => {
+            to_binary(&query_get_referral_stats(_deps, address)?)
+        }
         QueryMsg::GetAdmin {} => to_binary(&query_get_admin(_deps)?),
+        QueryMsg::GetPause {} => to_binary(&query_get_pause(_deps)?),
+        QueryMsg::GetAccess {} => to_binary(&query_get_access(_deps)?),
+        QueryMsg::ListVerifiers {} => to_binary(&query_list_verifiers(_deps)?),
+        QueryMsg::ListOracles { project_id } => to_binary(&query_list_oracles(_deps, project_id)?),
+        QueryMsg::ListArbitrators {} => to_binary(&query_list_arbitrators(_deps)?),
+        QueryMsg::GetDispute { dispute_id } => to_binary(&query_get_dispute(_deps, dispute_id)?),
+        QueryMsg::ListDisputes {
+            project_id,
+            status,
+            start_after,
+            limit,
+        } => to_binary(&query_list_disputes(
+            _deps,
+            project_id,
+            status,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ListModeration { project_id } => {
+            to_binary(&query_list_moderation(_deps, project_id)?)
+        }
     }
 }
 
@@ -119,6 +459,7 @@ pub fn execute_register_user(
         project_funded: vec![],
         project_watched: vec![],
         project_owned: vec![],
+        verification: None,
     };
     let mut users = USERS.load(_deps.storage).unwrap_or_default();
     //if load fail, create new
@@ -127,7 +468,9 @@ pub fn execute_register_user(
     }
     users.push(user);
     USERS.save(_deps.storage, &users)?;
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("action", ACTION_REGISTER_USER)
+        .add_event(neariot_event(ACTION_REGISTER_USER, &_info.sender)))
 }
 
 pub fn execute_create_project(
@@ -136,6 +479,7 @@ pub fn execute_create_project(
     _info: MessageInfo,
     _metadata: String,
 ) -> Result<Response, ContractError> {
+    assert_can_create(_deps.storage, &_info.sender)?;
     let block_info = _env.block.clone();
     let project = Project {
         owner: _info.sender.clone(),
@@ -146,8 +490,25 @@ pub fn execute_create_project(
         watchers: vec![],
         offers: vec![],
         total_pledged: Uint128::zero(),
+        goal: Uint128::zero(),
+        goal_denom: None,
+        withdrawn: vec![],
         bougth_offers: vec![],
+        min_pledge: Uint128::zero(),
+        referral_share: Decimal::zero(),
+        milestones: vec![],
+        frozen: vec![],
+        collaborators: vec![],
+        pending_owner: None,
+        pledges: vec![],
+        cancellation: CancellationPolicy::default(),
+        cancel_fees: vec![],
+        subscription_plan: None,
+        stretch_goals: vec![],
         milestone: block_info.time,
+        hidden: false,
+        under_review: false,
+        status: ProjectStatus::Draft,
     };
     let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
     //if load fail, create new
@@ -168,8 +529,13 @@ pub fn execute_create_project(
     projects.push(project.clone());
     PROJECTS.save(_deps.storage, &projects)?;
     let res = Response::new()
-        .add_attribute("action", "create_project")
-        .add_attribute("project_id", project.clone().id);
+        .add_attribute("action", ACTION_CREATE_PROJECT)
+        .add_attribute("project_id", project.clone().id)
+        .add_event(
+            neariot_event(ACTION_CREATE_PROJECT, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, project.id)
+                .add_attribute(ATTR_NEW_VALUE, project.metadata),
+        );
     Ok(res)
 }
 
@@ -181,22 +547,66 @@ pub fn execute_update_project(
     _metadata: String,
 ) -> Result<Response, ContractError> {
     let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
-    //if load fail, create new
-    assert!(projects.is_empty(), "project not found");
-    assert!(
-        projects.iter().any(|project| project.id == _id),
-        "project not found"
-    );
+    if !projects.iter().any(|project| project.id == _id) {
+        return Err(ContractError::ProjectNotFound {});
+    }
+    let old_metadata = projects
+        .iter()
+        .find(|project| project.id == _id)
+        .unwrap()
+        .metadata
+        .clone();
+    assert_role(&projects, &_id, &_info.sender, Some(Role::Editor))?;
+    assert_status(
+        &_env,
+        projects
+            .iter_mut()
+            .find(|project| project.id == _id)
+            .unwrap(),
+        EDITABLE,
+    )?;
 
     projects.iter_mut().for_each(|project| {
         if project.id == _id {
-            assert!(project.owner == _info.sender, "not owner");
             project.metadata = _metadata.to_owned();
         }
     });
 
     PROJECTS.save(_deps.storage, &projects)?;
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("action", ACTION_UPDATE_PROJECT)
+        .add_event(
+            neariot_event(ACTION_UPDATE_PROJECT, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_OLD_VALUE, old_metadata)
+                .add_attribute(ATTR_NEW_VALUE, _metadata),
+        ))
+}
+
+pub fn execute_launch_project(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if project.owner != _info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    transition(project, ProjectStatus::Live)?;
+    update_reputation(_deps.storage, &project.owner, |reputation| {
+        reputation.campaigns += 1
+    })?;
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_LAUNCH_PROJECT)
+        .add_event(
+            neariot_event(ACTION_LAUNCH_PROJECT, &_info.sender).add_attribute(ATTR_PROJECT_ID, _id),
+        ))
 }
 
 pub fn execute_update_timestamp(
@@ -207,21 +617,208 @@ pub fn execute_update_timestamp(
     _timestamp: u64,
 ) -> Result<Response, ContractError> {
     let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
-    //if load fail, create new
-    assert!(projects.is_empty(), "project not found");
-    assert!(
-        projects.iter().any(|project| project.id == _id),
-        "project not found"
-    );
+    if !projects.iter().any(|project| project.id == _id) {
+        return Err(ContractError::ProjectNotFound {});
+    }
+    let old_milestone = projects
+        .iter()
+        .find(|project| project.id == _id)
+        .unwrap()
+        .milestone;
+    assert_role(&projects, &_id, &_info.sender, Some(Role::Editor))?;
+    assert_status(
+        &_env,
+        projects
+            .iter_mut()
+            .find(|project| project.id == _id)
+            .unwrap(),
+        &[ProjectStatus::Draft],
+    )?;
     projects.iter_mut().for_each(|project| {
         if project.id == _id {
-            assert!(project.owner == _info.sender, "not owner");
             project.milestone = Timestamp::from_seconds(_timestamp.to_owned());
         }
     });
 
     PROJECTS.save(_deps.storage, &projects)?;
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("action", ACTION_UPDATE_TIMESTAMP)
+        .add_event(
+            neariot_event(ACTION_UPDATE_TIMESTAMP, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_OLD_VALUE, old_milestone.seconds().to_string())
+                .add_attribute(ATTR_NEW_VALUE, _timestamp.to_string()),
+        ))
+}
+
+pub fn execute_update_goal(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _goal: Uint128,
+    _denom: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Editor) {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_status(&_env, project, &[ProjectStatus::Draft])?;
+    let event = neariot_event(ACTION_UPDATE_GOAL, &_info.sender)
+        .add_attribute(ATTR_PROJECT_ID, _id)
+        .add_attribute(ATTR_OLD_VALUE, project.goal)
+        .add_attribute(ATTR_NEW_VALUE, _goal);
+    project.goal = _goal;
+    if _denom.is_some() {
+        project.goal_denom = _denom;
+    }
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_UPDATE_GOAL)
+        .add_event(event))
+}
+
+pub fn execute_set_collaborator(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _address: String,
+    _roles: Vec<Role>,
+) -> Result<Response, ContractError> {
+    let address = _deps.api.addr_validate(&_address)?;
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    assert_role(&projects, &_id, &_info.sender, None)?;
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .unwrap();
+    // roles go out as comma separated lists, empty when there are none
+    let role_names = |roles: &[Role]| roles.iter().map(Role::as_str).collect::<Vec<_>>().join(",");
+    let old_roles = project
+        .collaborators
+        .iter()
+        .find(|collaborator| collaborator.address == address)
+        .map_or(String::new(), |collaborator| {
+            role_names(&collaborator.roles)
+        });
+    let event = neariot_event(ACTION_SET_COLLABORATOR, &_info.sender)
+        .add_attribute(ATTR_PROJECT_ID, _id)
+        .add_attribute(ATTR_COLLABORATOR, address.to_string())
+        .add_attribute(ATTR_OLD_VALUE, old_roles)
+        .add_attribute(ATTR_NEW_VALUE, role_names(&_roles));
+    project
+        .collaborators
+        .retain(|collaborator| collaborator.address != address);
+    if !_roles.is_empty() {
+        project.collaborators.push(Collaborator {
+            address,
+            roles: _roles,
+        });
+    }
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_SET_COLLABORATOR)
+        .add_event(event))
+}
+
+pub fn execute_propose_ownership_transfer(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _new_owner: Option<String>,
+) -> Result<Response, ContractError> {
+    let new_owner = _new_owner
+        .map(|address| _deps.api.addr_validate(&address))
+        .transpose()?;
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    assert_role(&projects, &_id, &_info.sender, None)?;
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .unwrap();
+    if new_owner.as_ref() == Some(&project.owner) {
+        return Err(ContractError::InvalidNewOwner {});
+    }
+    let event = neariot_event(ACTION_PROPOSE_OWNERSHIP_TRANSFER, &_info.sender)
+        .add_attribute(ATTR_PROJECT_ID, _id)
+        .add_attribute(
+            ATTR_OLD_VALUE,
+            project
+                .pending_owner
+                .as_ref()
+                .map(Addr::to_string)
+                .unwrap_or_default(),
+        )
+        .add_attribute(
+            ATTR_NEW_VALUE,
+            new_owner.as_ref().map(Addr::to_string).unwrap_or_default(),
+        );
+    project.pending_owner = new_owner;
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_PROPOSE_OWNERSHIP_TRANSFER)
+        .add_event(event))
+}
+
+/// Hands the project over to its pending owner. Unwithdrawn funds, frozen
+/// escrow and subscriptions stay on the project and are claimed by the new
+/// owner, and the project's whole record moves to the new owner's
+/// reputation: launch, cancel, backings, ratings, deliveries and disputes.
+pub fn execute_accept_ownership_transfer(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+) -> Result<Response, ContractError> {
+    assert_can_create(_deps.storage, &_info.sender)?;
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if project.pending_owner.as_ref() != Some(&_info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let old_owner = std::mem::replace(&mut project.owner, _info.sender.clone());
+    project.pending_owner = None;
+    project
+        .collaborators
+        .retain(|collaborator| collaborator.address != _info.sender);
+    // the project's record moves with it, so later updates land on one owner
+    let record = project_reputation(_deps.storage, project)?;
+    PROJECTS.save(_deps.storage, &projects)?;
+
+    let mut users = USERS.load(_deps.storage).unwrap_or_default();
+    users.iter_mut().for_each(|user| {
+        if user.address == old_owner {
+            user.project_owned.retain(|id| id != &_id);
+        } else if user.address == _info.sender && !user.project_owned.contains(&_id) {
+            user.project_owned.push(_id.clone());
+        }
+    });
+    USERS.save(_deps.storage, &users)?;
+
+    update_reputation(_deps.storage, &old_owner, |reputation| {
+        reputation.remove(&record)
+    })?;
+    update_reputation(_deps.storage, &_info.sender, |reputation| {
+        reputation.add(&record)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", ACTION_ACCEPT_OWNERSHIP_TRANSFER)
+        .add_event(
+            neariot_event(ACTION_ACCEPT_OWNERSHIP_TRANSFER, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_OLD_VALUE, old_owner)
+                .add_attribute(ATTR_NEW_VALUE, _info.sender.to_string()),
+        ))
 }
 
 pub fn execute_create_project_offer(
@@ -234,30 +831,46 @@ pub fn execute_create_project_offer(
     _expire: u64,
 ) -> Result<Response, ContractError> {
     let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
-    //if load fail, create new
-    assert!(projects.is_empty(), "project not found");
-    assert!(
-        projects.iter().any(|project| project.id == _id),
-        "project not found"
-    );
+    if !projects.iter().any(|project| project.id == _id) {
+        return Err(ContractError::ProjectNotFound {});
+    }
+    assert_role(&projects, &_id, &_info.sender, Some(Role::Editor))?;
+    assert_status(
+        &_env,
+        projects
+            .iter_mut()
+            .find(|project| project.id == _id)
+            .unwrap(),
+        EDITABLE,
+    )?;
+    let offer_id = generate_id(_info.sender.clone(), _env.block.clone());
     projects.iter_mut().for_each(|project| {
         if project.id == _id {
-            assert!(project.owner == _info.sender.clone(), "not owner");
             let offer = Offer {
-                id: generate_id(_info.sender.clone(), _env.block.clone()),
+                id: offer_id.clone(),
                 metadata: _metadata.clone(),
                 min_price: _price,
                 create_at: _env.block.time,
                 expire_at: Timestamp::from_seconds(_expire),
+                active: true,
+                price_schedule: vec![],
             };
             project.offers.push(offer);
         }
     });
 
     PROJECTS.save(_deps.storage, &projects)?;
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("action", ACTION_CREATE_OFFER)
+        .add_event(
+            neariot_event(ACTION_CREATE_OFFER, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_OFFER_ID, offer_id)
+                .add_attribute(ATTR_AMOUNT, _price),
+        ))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_update_project_offer(
     _deps: DepsMut,
     _env: Env,
@@ -269,15 +882,29 @@ pub fn execute_update_project_offer(
     _expire: u64,
 ) -> Result<Response, ContractError> {
     let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
-    //if load fail, create new
-    assert!(projects.is_empty(), "project not found");
-    assert!(
-        projects.iter().any(|project| project.id == _id),
-        "project not found"
-    );
+    if !projects.iter().any(|project| project.id == _id) {
+        return Err(ContractError::ProjectNotFound {});
+    }
+    let old_price = projects
+        .iter()
+        .find(|project| project.id == _id)
+        .unwrap()
+        .offers
+        .iter()
+        .find(|offer| offer.id == _offer_id)
+        .map(|offer| offer.min_price)
+        .unwrap_or_default();
+    assert_role(&projects, &_id, &_info.sender, Some(Role::Editor))?;
+    assert_status(
+        &_env,
+        projects
+            .iter_mut()
+            .find(|project| project.id == _id)
+            .unwrap(),
+        EDITABLE,
+    )?;
     projects.iter_mut().for_each(|project| {
         if project.id == _id {
-            assert!(project.owner == _info.sender, "not owner");
             project.offers.iter_mut().for_each(|offer| {
                 if offer.id == _offer_id {
                     offer.min_price = _price;
@@ -289,7 +916,62 @@ pub fn execute_update_project_offer(
     });
 
     PROJECTS.save(_deps.storage, &projects)?;
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("action", ACTION_UPDATE_OFFER)
+        .add_event(
+            neariot_event(ACTION_UPDATE_OFFER, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_OFFER_ID, _offer_id)
+                .add_attribute(ATTR_OLD_VALUE, old_price)
+                .add_attribute(ATTR_NEW_VALUE, _price),
+        ))
+}
+
+pub fn execute_update_offer_pricing(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _offer_id: String,
+    _schedule: Vec<PriceTier>,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Editor) {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_status(&_env, project, EDITABLE)?;
+    let sold = offers_sold(project, &_offer_id);
+    let offer = project
+        .offers
+        .iter_mut()
+        .find(|offer| offer.id == _offer_id)
+        .ok_or(ContractError::OfferNotFound {})?;
+    let old_price = offer.current_price(_env.block.time, sold);
+    offer.price_schedule = _schedule;
+    let new_price = offer.current_price(_env.block.time, sold);
+
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_UPDATE_OFFER_PRICING)
+        .add_event(
+            neariot_event(ACTION_UPDATE_OFFER_PRICING, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_OFFER_ID, _offer_id)
+                .add_attribute(ATTR_OLD_VALUE, old_price)
+                .add_attribute(ATTR_NEW_VALUE, new_price),
+        ))
+}
+
+fn offers_sold(project: &Project, offer_id: &str) -> u32 {
+    project
+        .bougth_offers
+        .iter()
+        .filter(|bougth_offer| bougth_offer.offer_id == offer_id)
+        .count() as u32
 }
 
 pub fn execute_delete_project_offer(
@@ -300,23 +982,35 @@ pub fn execute_delete_project_offer(
     _offer_id: String,
 ) -> Result<Response, ContractError> {
     let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
-    //if load fail, create new
-    assert!(projects.is_empty(), "project not found");
-    assert!(
-        projects.iter().any(|project| project.id == _id),
-        "project not found"
-    );
+    if !projects.iter().any(|project| project.id == _id) {
+        return Err(ContractError::ProjectNotFound {});
+    }
+    assert_role(&projects, &_id, &_info.sender, Some(Role::Editor))?;
+    assert_status(
+        &_env,
+        projects
+            .iter_mut()
+            .find(|project| project.id == _id)
+            .unwrap(),
+        EDITABLE,
+    )?;
     projects.iter_mut().for_each(|project| {
         if project.id == _id {
-            assert!(project.owner == _info.sender, "not owner");
             project.offers.retain(|offer| offer.id != _offer_id);
         }
     });
 
     PROJECTS.save(_deps.storage, &projects)?;
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("action", ACTION_DELETE_OFFER)
+        .add_event(
+            neariot_event(ACTION_DELETE_OFFER, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_OFFER_ID, _offer_id),
+        ))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_buy_project_offer(
     _deps: DepsMut,
     _env: Env,
@@ -325,41 +1019,122 @@ pub fn execute_buy_project_offer(
     _offer_id: String,
     _metadata: String,
     _rate: Uint128,
+    _promo_code: Option<String>,
+    _referrer: Option<String>,
 ) -> Result<Response, ContractError> {
+    assert_can_buy(_deps.storage, &_info.sender)?;
     let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
-    //if load fail, create new
-    assert!(projects.is_empty(), "project not found");
-    assert!(
-        projects.iter().any(|project| project.id == _id),
-        "project not found"
-    );
-    let amount = _info.funds[0].amount;
-    projects.iter_mut().for_each(|project| {
-        if project.id == _id {
-            let offer = project
-                .offers
-                .iter()
-                .find(|offer| offer.id == _offer_id)
-                .unwrap();
-            assert!(
-                amount >= offer.min_price,
-                "{}",
-                ContractError::InsufficientFunds {}
-            );
-            let bougth_offer = BougthOffer {
-                id: generate_id(_info.sender.clone(), _env.block.clone()),
-                price: amount,
-                buyer: _info.sender.clone(),
-                create_at: _env.block.time,
-                rate: _rate,
-                metadata: _metadata.clone(),
-            };
-            project.bougth_offers.push(bougth_offer);
+    if !projects.iter().any(|project| project.id == _id) {
+        return Err(ContractError::ProjectNotFound {});
+    }
+    let payment = one_coin(&_info)?;
+    let amount = payment.amount;
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .unwrap();
+    assert_status(&_env, project, &[ProjectStatus::Live])?;
+    if project.under_review {
+        return Err(ContractError::ProjectUnderReview {});
+    }
+    assert_funding_denom(project, &payment.denom)?;
+    let offer = project
+        .offers
+        .iter()
+        .find(|offer| offer.id == _offer_id)
+        .ok_or(ContractError::OfferNotFound {})?;
+    if !offer.active || offer.is_expired(_env.block.time) {
+        return Err(ContractError::OfferInactive {});
+    }
+    let mut price = offer.current_price(_env.block.time, offers_sold(project, &_offer_id));
+    let promo_hash = match _promo_code {
+        Some(code) => {
+            let hash = hash_code(&code);
+            let mut promo = PROMO_CODES
+                .may_load(_deps.storage, (&_id, hash.as_slice()))?
+                .ok_or(ContractError::InvalidPromoCode {})?;
+            if promo
+                .expire_at
+                .map_or(false, |expire_at| _env.block.time >= expire_at)
+            {
+                return Err(ContractError::PromoCodeExpired {});
+            }
+            if promo.used >= promo.max_uses {
+                return Err(ContractError::PromoCodeExhausted {});
+            }
+            price = promo.discount.apply(price);
+            promo.used += 1;
+            PROMO_CODES.save(_deps.storage, (&_id, hash.as_slice()), &promo)?;
+            Some(hash)
         }
-    });
+        None => None,
+    };
+    if amount < price {
+        return Err(ContractError::InsufficientFunds {});
+    }
+    let referral = match _referrer {
+        Some(referrer) => {
+            let referrer = _deps.api.addr_validate(&referrer)?;
+            if referrer == _info.sender || referrer == project.owner {
+                return Err(ContractError::InvalidReferrer {});
+            }
+            let mut stats = REFERRAL_STATS
+                .may_load(_deps.storage, &referrer)?
+                .unwrap_or_default();
+            let mut volume = NativeBalance(stats.volume);
+            volume += payment.clone();
+            stats.referrals += 1;
+            stats.volume = volume.into_vec();
+            REFERRAL_STATS.save(_deps.storage, &referrer, &stats)?;
+            Some(Referral {
+                referrer,
+                share: project.referral_share,
+                settled: false,
+            })
+        }
+        None => None,
+    };
+    let bougth_offer = BougthOffer {
+        id: next_id(_deps.storage)?,
+        offer_id: _offer_id.clone(),
+        price: amount,
+        offer_price: price,
+        denom: payment.denom.clone(),
+        buyer: _info.sender.clone(),
+        create_at: _env.block.time,
+        rate: _rate,
+        metadata: _metadata.clone(),
+        extras: project
+            .stretch_goals
+            .iter()
+            .filter(|goal| goal.reached_at.is_some())
+            .map(|goal| goal.metadata.clone())
+            .collect(),
+        promo_code: promo_hash,
+        referral,
+        shipment: None,
+    };
+    let event = neariot_event(ACTION_BUY_OFFER, &_info.sender)
+        .add_attribute(ATTR_PROJECT_ID, _id.clone())
+        .add_attribute(ATTR_OFFER_ID, _offer_id)
+        .add_attribute(ATTR_PURCHASE_ID, bougth_offer.id.clone())
+        .add_attribute(ATTR_AMOUNT, amount)
+        .add_attribute(ATTR_DENOM, payment.denom.clone())
+        .add_attribute(ATTR_OLD_VALUE, project.total_pledged)
+        .add_attribute(ATTR_NEW_VALUE, project.total_pledged + amount);
+    project.total_pledged += amount;
+    project.bougth_offers.push(bougth_offer);
+    let reached = apply_stretch_goals(project, &_env, &_info.sender);
+    update_reputation(_deps.storage, &project.owner, |reputation| {
+        reputation.backings += 1;
+        if !_rate.is_zero() {
+            reputation.rating_sum += _rate;
+            reputation.rating_count += 1;
+        }
+    })?;
     // let user = USERS.load(_deps.storage)?;
     // let mut user = user;
-    // user.total_spent = user.total_spent + amount;
+    // user.total_spent += amount;
     // user.project_funded.iter().for_each(|project|{
     //     if project != &_id{
     //         user.project_funded.push(_id);
@@ -371,284 +1146,6037 @@ pub fn execute_buy_project_offer(
     //     }
     // });
     // USERS.save(_deps.storage,  &user)?;
-    let mut users = USERS.load(_deps.storage).unwrap_or_default();
-    users.iter_mut().for_each(|user| {
-        if user.address == _info.sender {
-            user.total_spent = user.total_spent + amount;
-            user.clone().project_funded.iter_mut().for_each(|project| {
-                if project != &_id {
-                    user.project_funded.push(_id.clone());
-                }
-            });
-            user.clone().project_watched.iter().for_each(|project| {
-                if project != &_id {
-                    user.project_watched.push(_id.clone());
-                }
-            });
-        }
-    });
+    record_contribution(_deps.storage, &_env, &_id, &_info.sender, payment)?;
     PROJECTS.save(_deps.storage, &projects)?;
-    Ok(Response::default())
-}
-
-pub fn must_pay_funds(balance: &NativeBalance, denom: &str) -> Result<Uint128, ContractError> {
-    match balance.0.len() {
-        0 => Err(ContractError::NoFunds {}),
-        1 => {
-            let balance = &balance.0;
-            let payment = balance[0].amount;
-            if balance[0].denom == denom {
-                Ok(payment)
-            } else {
-                Err(ContractError::MissingDenom(denom.to_string()))
-            }
-        }
-        _ => Err(ContractError::ExtraDenoms(denom.to_string())),
-    }
+    Ok(Response::new()
+        .add_attribute("action", ACTION_BUY_OFFER)
+        .add_event(event)
+        .add_events(reached))
 }
 
-pub fn execute_rate_project_offer(
+#[allow(clippy::too_many_arguments)]
+pub fn execute_add_promo_code(
     _deps: DepsMut,
     _env: Env,
     _info: MessageInfo,
     _id: String,
-    _offer_id: String,
-    _rate: Uint128,
+    _code_hash: Binary,
+    _discount: Discount,
+    _max_uses: u32,
+    _expire_at: Option<u64>,
 ) -> Result<Response, ContractError> {
     let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
-    //if load fail, create new
-    assert!(projects.is_empty(), "project not found");
-    assert!(
-        projects.iter().any(|project| project.id == _id),
-        "project not found"
-    );
-    projects.iter_mut().for_each(|project| {
-        if project.id == _id {
-            project.bougth_offers.iter_mut().for_each(|offer| {
-                if offer.id == _offer_id {
-                    offer.rate = _rate;
-                }
-            });
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Editor) {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_status(&_env, project, EDITABLE)?;
+    if let Discount::Percent(percent) = _discount {
+        if percent > Decimal::one() {
+            return Err(ContractError::InvalidAmount {});
         }
-    });
-    PROJECTS.save(_deps.storage, &projects)?;
-    Ok(Response::default())
+    }
+    if _max_uses == 0 {
+        return Err(ContractError::InvalidAmount {});
+    }
+    let promo = PromoCode {
+        hash: _code_hash.clone(),
+        discount: _discount,
+        max_uses: _max_uses,
+        used: 0,
+        expire_at: _expire_at.map(Timestamp::from_seconds),
+    };
+    PROMO_CODES.save(_deps.storage, (&_id, _code_hash.as_slice()), &promo)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_ADD_PROMO_CODE)
+        .add_event(
+            neariot_event(ACTION_ADD_PROMO_CODE, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_NEW_VALUE, _code_hash.to_base64()),
+        ))
 }
 
-pub fn execute_watch_project(
+pub fn execute_remove_promo_code(
     _deps: DepsMut,
     _env: Env,
     _info: MessageInfo,
     _id: String,
+    _code_hash: Binary,
 ) -> Result<Response, ContractError> {
     let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
-    //if load fail, create new
-    assert!(projects.is_empty(), "project not found");
-    assert!(
-        projects.iter().any(|project| project.id == _id),
-        "project not found"
-    );
-    projects.iter_mut().for_each(|project| {
-        if project.id == _id {
-            project.watchers.iter().for_each(|watcher| {
-                if watcher.eq(&_info.sender.clone()) {
-                    project.clone().watchers.push(_info.sender.clone());
-                }
-            });
-        }
-    });
-    // let mut user = USERS.load(_deps.storage)?;
-    // user.project_watched.iter().for_each(|project|{
-    //     if project != &_id{
-    //         user.project_watched.push(_id);
-    //     }
-    // });
-    let mut users = USERS.load(_deps.storage).unwrap_or_default();
-    users.iter_mut().for_each(|user| {
-        if user.address == _info.sender {
-            user.clone().project_watched.iter().for_each(|project| {
-                if !project.clone().eq(&_id.clone()) {
-                    user.project_watched.push(_id.clone());
-                }
-            });
-        }
-    });
-    USERS.save(_deps.storage, &users)?;
-    PROJECTS.save(_deps.storage, &projects)?;
-    Ok(Response::default())
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Editor) {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_status(&_env, project, EDITABLE)?;
+    if !PROMO_CODES.has(_deps.storage, (&_id, _code_hash.as_slice())) {
+        return Err(ContractError::InvalidPromoCode {});
+    }
+    PROMO_CODES.remove(_deps.storage, (&_id, _code_hash.as_slice()));
+    Ok(Response::new()
+        .add_attribute("action", ACTION_REMOVE_PROMO_CODE)
+        .add_event(
+            neariot_event(ACTION_REMOVE_PROMO_CODE, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_OLD_VALUE, _code_hash.to_base64()),
+        ))
 }
 
-pub fn execute_unwatch_project(
+pub fn execute_add_milestone(
     _deps: DepsMut,
     _env: Env,
     _info: MessageInfo,
     _id: String,
+    _metadata: String,
+    _tranche: Decimal,
+    _condition: MilestoneCondition,
 ) -> Result<Response, ContractError> {
     let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
-    //if load fail, create new
-    assert!(projects.is_empty(), "project not found");
-    assert!(
-        projects.iter().any(|project| project.id == _id),
-        "project not found"
-    );
-    projects.iter_mut().for_each(|project| {
-        if project.id == _id {
-            project.watchers.retain(|watcher| watcher != &_info.sender);
-        }
-    });
-    // let mut user = USERS.load(_deps.storage)?;
-    // user.project_watched.retain(|project| project != &_id);
-    let mut users = USERS.load(_deps.storage).unwrap_or_default();
-    users.iter_mut().for_each(|user| {
-        if user.address == _info.sender {
-            user.project_watched.retain(|project| project != &_id);
-        }
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Editor) {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_status(&_env, project, EDITABLE)?;
+    let assigned = project
+        .milestones
+        .iter()
+        .fold(_tranche, |total, milestone| total + milestone.tranche);
+    if assigned > Decimal::one() {
+        return Err(ContractError::InvalidShare {});
+    }
+    if let MilestoneCondition::Oracle { quorum: 0 } = _condition {
+        return Err(ContractError::InvalidAmount {});
+    }
+    let milestone_id = next_id(_deps.storage)?;
+    project.milestones.push(Milestone {
+        id: milestone_id.clone(),
+        metadata: _metadata,
+        tranche: _tranche,
+        condition: _condition,
+        completed_at: None,
+        failed_at: None,
+        votes: vec![],
     });
-    USERS.save(_deps.storage, &users)?;
     PROJECTS.save(_deps.storage, &projects)?;
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("action", ACTION_ADD_MILESTONE)
+        .add_event(
+            neariot_event(ACTION_ADD_MILESTONE, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_MILESTONE_ID, milestone_id),
+        ))
 }
 
-pub fn execute_rate_project(
+pub fn execute_mark_shipped(
     _deps: DepsMut,
     _env: Env,
     _info: MessageInfo,
-    _id: String,
-    _rate: Uint128,
+    _project_id: String,
+    _purchase_id: String,
+    _tracking_hash: Option<Binary>,
 ) -> Result<Response, ContractError> {
     let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
-    //if load fail, create new
-    assert!(projects.is_empty(), "project not found");
-    assert!(
-        projects.iter().any(|project| project.id == _id),
-        "project not found"
-    );
-    projects.iter_mut().for_each(|project| {
-        if project.id == _id {
-            project.avg_rate = project
-                .bougth_offers
-                .iter()
-                .fold(Uint128::zero(), |acc, offer| acc + offer.rate)
-                / Uint128::from(project.bougth_offers.len() as u128);
-        }
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _project_id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Reporter) {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_status(&_env, project, FULFILLING)?;
+    if project.status == ProjectStatus::Funded {
+        transition(project, ProjectStatus::Delivering)?;
+    }
+    let owner = project.owner.clone();
+    let purchase = project
+        .bougth_offers
+        .iter_mut()
+        .find(|offer| offer.id == _purchase_id)
+        .ok_or(ContractError::PurchaseNotFound {})?;
+    if purchase
+        .shipment
+        .as_ref()
+        .map_or(false, |shipment| shipment.confirmed_at.is_some())
+    {
+        return Err(ContractError::AlreadyConfirmed {});
+    }
+    let mut event = neariot_event(ACTION_MARK_SHIPPED, &_info.sender)
+        .add_attribute(ATTR_PROJECT_ID, _project_id)
+        .add_attribute(ATTR_PURCHASE_ID, _purchase_id);
+    if let Some(tracking_hash) = &_tracking_hash {
+        event = event.add_attribute(ATTR_NEW_VALUE, tracking_hash.to_base64());
+    }
+    if purchase.shipment.is_none() {
+        update_reputation(_deps.storage, &owner, |reputation| {
+            reputation.shipments += 1
+        })?;
+    }
+    purchase.shipment = Some(Shipment {
+        shipped_at: _env.block.time,
+        tracking_hash: _tracking_hash,
+        confirmed_at: None,
     });
     PROJECTS.save(_deps.storage, &projects)?;
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("action", ACTION_MARK_SHIPPED)
+        .add_event(event))
 }
 
-pub fn query_get_user(_deps: Deps, _id: Addr) -> StdResult<User> {
-    let users = USERS.load(_deps.storage)?;
-    let user = users.iter().find(|user| user.name == _id).unwrap();
-    Ok(user.to_owned())
-}
-
-pub fn query_list_user(_deps: Deps) -> StdResult<Vec<User>> {
-    let users = USERS.load(_deps.storage)?;
-    Ok(users.to_owned())
+pub fn execute_confirm_delivery(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _project_id: String,
+    _purchase_id: String,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _project_id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    assert_status(
+        &_env,
+        project,
+        &[ProjectStatus::Delivering, ProjectStatus::Completed],
+    )?;
+    let purchase = project
+        .bougth_offers
+        .iter_mut()
+        .find(|offer| offer.id == _purchase_id && offer.buyer == _info.sender)
+        .ok_or(ContractError::PurchaseNotFound {})?;
+    let shipment = purchase
+        .shipment
+        .as_mut()
+        .ok_or(ContractError::NotShipped {})?;
+    if shipment.confirmed_at.is_some() {
+        return Err(ContractError::AlreadyConfirmed {});
+    }
+    shipment.confirmed_at = Some(_env.block.time);
+    update_reputation(_deps.storage, &project.owner, |reputation| {
+        reputation.confirmed_deliveries += 1
+    })?;
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_CONFIRM_DELIVERY)
+        .add_event(
+            neariot_event(ACTION_CONFIRM_DELIVERY, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _project_id)
+                .add_attribute(ATTR_PURCHASE_ID, _purchase_id),
+        ))
 }
 
-pub fn query_get_project(_deps: Deps, _id: String) -> StdResult<Project> {
-    let projects = PROJECTS.load(_deps.storage)?;
-    let project = projects.iter().find(|project| project.id == _id).unwrap();
-    Ok(project.to_owned())
+pub fn execute_attest_milestone(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _project_id: String,
+    _milestone_id: String,
+    _met: bool,
+    _evidence_uri: String,
+) -> Result<Response, ContractError> {
+    assert_oracle(_deps.storage, &_project_id, &_info.sender)?;
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _project_id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    assert_status(&_env, project, FULFILLING)?;
+    let milestone = project
+        .milestones
+        .iter_mut()
+        .find(|milestone| milestone.id == _milestone_id)
+        .ok_or(ContractError::MilestoneNotFound {})?;
+    if !milestone.is_open() {
+        return Err(ContractError::MilestoneSettled {});
+    }
+    milestone.votes.retain(|vote| vote.oracle != _info.sender);
+    milestone.votes.push(OracleVote {
+        oracle: _info.sender.clone(),
+        met: _met,
+        evidence_uri: _evidence_uri.clone(),
+        create_at: _env.block.time,
+    });
+    let settled = apply_milestones(_deps.storage, &_env, project, &_info.sender)?;
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_ATTEST_MILESTONE)
+        .add_event(
+            neariot_event(ACTION_ATTEST_MILESTONE, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _project_id)
+                .add_attribute(ATTR_MILESTONE_ID, _milestone_id)
+                .add_attribute(ATTR_NEW_VALUE, _met.to_string())
+                .add_attribute(ATTR_EVIDENCE_URI, _evidence_uri),
+        )
+        .add_events(settled))
 }
 
-pub fn query_list_project(_deps: Deps) -> StdResult<Vec<Project>> {
-    let projects = PROJECTS.load(_deps.storage)?;
-    Ok(projects.to_owned())
+/// Settles every open milestone whose condition holds: completion unlocks its
+/// tranche, failure keeps it locked. Milestones only settle once funded.
+fn apply_milestones(
+    storage: &dyn Storage,
+    env: &Env,
+    project: &mut Project,
+    actor: &Addr,
+) -> StdResult<Vec<Event>> {
+    let mut events = vec![];
+    if !FULFILLING.contains(&project.status) {
+        return Ok(events);
+    }
+    for milestone in project.milestones.iter_mut() {
+        if !milestone.is_open() {
+            continue;
+        }
+        let (met, failed) = match &milestone.condition {
+            MilestoneCondition::Telemetry { min_devices, after } => (
+                attested_devices(storage, &project.id, *after)? >= *min_devices,
+                false,
+            ),
+            MilestoneCondition::Oracle { quorum } => {
+                let met_votes = milestone.votes.iter().filter(|vote| vote.met).count() as u32;
+                let failed_votes = milestone.votes.len() as u32 - met_votes;
+                (met_votes >= *quorum, failed_votes >= *quorum)
+            }
+        };
+        let action = if met {
+            milestone.completed_at = Some(env.block.time);
+            ACTION_COMPLETE_MILESTONE
+        } else if failed {
+            milestone.failed_at = Some(env.block.time);
+            ACTION_FAIL_MILESTONE
+        } else {
+            continue;
+        };
+        events.push(
+            neariot_event(action, actor)
+                .add_attribute(ATTR_PROJECT_ID, project.id.clone())
+                .add_attribute(ATTR_MILESTONE_ID, milestone.id.clone()),
+        );
+    }
+    Ok(events)
 }
 
-pub fn query_get_project_offers(_deps: Deps, _id: String) -> StdResult<Vec<Offer>> {
-    let projects = PROJECTS.load(_deps.storage)?;
-    let project = projects.iter().find(|project| project.id == _id).unwrap();
-    Ok(project.offers.to_owned())
+fn assert_oracle(
+    storage: &dyn Storage,
+    project_id: &str,
+    sender: &Addr,
+) -> Result<(), ContractError> {
+    let global = GLOBAL_ORACLES.may_load(storage)?.unwrap_or_default();
+    let project = PROJECT_ORACLES
+        .may_load(storage, project_id)?
+        .unwrap_or_default();
+    if !global.contains(sender) && !project.contains(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
 }
 
-pub fn query_get_project_offer(_deps: Deps, _id: String, _offer_id: String) -> StdResult<Offer> {
-    let projects = PROJECTS.load(_deps.storage)?;
-    let project = projects.iter().find(|project| project.id == _id).unwrap();
-    let offer = project
-        .offers
+pub fn execute_open_dispute(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _project_id: String,
+    _purchase_id: String,
+    _reason: String,
+    _evidence_uri: String,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _project_id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    assert_status(
+        &_env,
+        project,
+        &[
+            ProjectStatus::Live,
+            ProjectStatus::Funded,
+            ProjectStatus::Delivering,
+        ],
+    )?;
+    let purchase = project
+        .bougth_offers
         .iter()
-        .find(|offer| offer.id == _offer_id)
-        .unwrap();
-    Ok(offer.to_owned())
-}
+        .find(|offer| offer.id == _purchase_id && offer.buyer == _info.sender)
+        .ok_or(ContractError::PurchaseNotFound {})?;
+    if OPEN_DISPUTES.has(_deps.storage, (&_project_id, &_purchase_id)) {
+        return Err(ContractError::DisputeAlreadyOpen {});
+    }
 
-pub fn query_get_admin(_deps: Deps) -> StdResult<Addr> {
-    let admin = ADMIN.load(_deps.storage)?;
-    Ok(admin.to_owned())
+    // after a withdrawal only part of the purchase is still held
+    let held = |balance: NativeBalance| {
+        balance
+            .into_vec()
+            .into_iter()
+            .find(|held| held.denom == purchase.denom)
+            .map_or(Uint128::zero(), |held| held.amount)
+    };
+    let raised = held(project_raised(project));
+    let escrow = held(project_escrow(project));
+    let frozen = if raised.is_zero() {
+        Uint128::zero()
+    } else {
+        purchase.price.multiply_ratio(escrow, raised)
+    };
+    let frozen = coin(frozen.u128(), &purchase.denom);
+
+    let dispute = Dispute {
+        id: next_id(_deps.storage)?,
+        project_id: _project_id.clone(),
+        purchase_id: _purchase_id.clone(),
+        backer: _info.sender.clone(),
+        reason: _reason,
+        evidence_uri: _evidence_uri.clone(),
+        frozen: frozen.clone(),
+        status: DisputeStatus::Open,
+        refunded: Uint128::zero(),
+        resolver: None,
+        create_at: _env.block.time,
+        resolved_at: None,
+    };
+    let mut project_frozen = NativeBalance(project.frozen.clone());
+    project_frozen += frozen.clone();
+    project_frozen.normalize();
+    project.frozen = project_frozen.into_vec();
+    update_reputation(_deps.storage, &project.owner, |reputation| {
+        reputation.disputes += 1
+    })?;
+    DISPUTES.save(_deps.storage, &dispute.id, &dispute)?;
+    OPEN_DISPUTES.save(_deps.storage, (&_project_id, &_purchase_id), &dispute.id)?;
+    PROJECT_DISPUTES.save(_deps.storage, (&_project_id, &dispute.id), &_purchase_id)?;
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_OPEN_DISPUTE)
+        .add_event(
+            neariot_event(ACTION_OPEN_DISPUTE, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _project_id)
+                .add_attribute(ATTR_PURCHASE_ID, _purchase_id)
+                .add_attribute(ATTR_DISPUTE_ID, dispute.id)
+                .add_attribute(ATTR_AMOUNT, frozen.amount)
+                .add_attribute(ATTR_DENOM, frozen.denom)
+                .add_attribute(ATTR_EVIDENCE_URI, _evidence_uri),
+        ))
 }
 
-// #[inline]
-// fn coin_to_string(amount: Uint128, denom: &str) -> String {
-//     format!("{} {}", amount, denom)
-// }
+/// Unfreezes the dispute's escrow. Any refund is taken off the purchase, as
+/// with `CancelPurchase`, and credited to the backer's claimable refunds.
+pub fn execute_resolve_dispute(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _dispute_id: String,
+    _resolution: DisputeResolution,
+) -> Result<Response, ContractError> {
+    let arbitrators = ARBITRATORS.may_load(_deps.storage)?.unwrap_or_default();
+    if !arbitrators.contains(&_info.sender) {
+        assert_admin(_deps.storage, &_info.sender)?;
+    }
+    let mut dispute = DISPUTES
+        .may_load(_deps.storage, &_dispute_id)?
+        .ok_or(ContractError::DisputeNotFound {})?;
+    if dispute.status != DisputeStatus::Open {
+        return Err(ContractError::DisputeResolved {});
+    }
+    let (status, refund) = match _resolution {
+        DisputeResolution::Refund => (DisputeStatus::Refunded, dispute.frozen.amount),
+        DisputeResolution::PartialRefund { amount } => {
+            if amount.is_zero() || amount > dispute.frozen.amount {
+                return Err(ContractError::InvalidAmount {});
+            }
+            (DisputeStatus::PartiallyRefunded, amount)
+        }
+        DisputeResolution::Reject => (DisputeStatus::Rejected, Uint128::zero()),
+    };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, from_binary, BankMsg, Coin, CosmosMsg};
+    let mut projects = PROJECTS.load(_deps.storage)?;
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == dispute.project_id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    // cancelling refunds the whole escrow, disputed purchases included
+    if project.status == ProjectStatus::Cancelled {
+        return Err(ContractError::ProjectCancelled {});
+    }
+    project.frozen = saturating_sub(
+        &NativeBalance(project.frozen.clone()),
+        &NativeBalance(vec![dispute.frozen.clone()]),
+    )
+    .into_vec();
+    let mut removed = false;
+    if let Some(purchase) = project
+        .bougth_offers
+        .iter_mut()
+        .find(|offer| offer.id == dispute.purchase_id)
+    {
+        purchase.price -= refund;
+        removed = purchase.price.is_zero();
+    }
+    if removed {
+        project
+            .bougth_offers
+            .retain(|offer| offer.id != dispute.purchase_id);
+    }
+    project.total_pledged = project.total_pledged.saturating_sub(refund);
+    update_reputation(_deps.storage, &project.owner, |reputation| {
+        if refund.is_zero() {
+            reputation.disputes_rejected += 1;
+        } else {
+            reputation.disputes_upheld += 1;
+            reputation.refunds += 1;
+        }
+    })?;
+    PROJECTS.save(_deps.storage, &projects)?;
+    if !refund.is_zero() {
+        let refund = coin(refund.u128(), &dispute.frozen.denom);
+        deduct_contribution(
+            _deps.storage,
+            &dispute.project_id,
+            &dispute.backer,
+            refund.clone(),
+            removed,
+        )?;
+        credit_refund(_deps.storage, &dispute.backer, refund)?;
+    }
 
-    #[test]
-    fn proper_initialization() {
-        let mut deps = mock_dependencies();
+    OPEN_DISPUTES.remove(_deps.storage, (&dispute.project_id, &dispute.purchase_id));
+    dispute.status = status;
+    dispute.refunded = refund;
+    dispute.resolver = Some(_info.sender.clone());
+    dispute.resolved_at = Some(_env.block.time);
+    DISPUTES.save(_deps.storage, &_dispute_id, &dispute)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_RESOLVE_DISPUTE)
+        .add_event(
+            neariot_event(ACTION_RESOLVE_DISPUTE, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, dispute.project_id)
+                .add_attribute(ATTR_DISPUTE_ID, _dispute_id)
+                .add_attribute(ATTR_AMOUNT, refund)
+                .add_attribute(ATTR_DENOM, dispute.frozen.denom),
+        ))
+}
 
-        let msg = InstantiateMsg {};
-        let info = mock_info("creator", &coins(1000, "orai"));
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+pub fn execute_add_arbitrator(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _address: String,
+) -> Result<Response, ContractError> {
+    assert_admin(_deps.storage, &_info.sender)?;
+    let arbitrator = _deps.api.addr_validate(&_address)?;
+    let mut arbitrators = ARBITRATORS.may_load(_deps.storage)?.unwrap_or_default();
+    if !arbitrators.contains(&arbitrator) {
+        arbitrators.push(arbitrator);
     }
+    ARBITRATORS.save(_deps.storage, &arbitrators)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_ADD_ARBITRATOR)
+        .add_event(
+            neariot_event(ACTION_ADD_ARBITRATOR, &_info.sender)
+                .add_attribute(ATTR_NEW_VALUE, _address),
+        ))
+}
 
-    #[test]
-    fn register_user() {
-        let mut deps = mock_dependencies();
+pub fn execute_remove_arbitrator(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _address: String,
+) -> Result<Response, ContractError> {
+    assert_admin(_deps.storage, &_info.sender)?;
+    let mut arbitrators = ARBITRATORS.may_load(_deps.storage)?.unwrap_or_default();
+    arbitrators.retain(|arbitrator| arbitrator.as_str() != _address);
+    ARBITRATORS.save(_deps.storage, &arbitrators)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_REMOVE_ARBITRATOR)
+        .add_event(
+            neariot_event(ACTION_REMOVE_ARBITRATOR, &_info.sender)
+                .add_attribute(ATTR_OLD_VALUE, _address),
+        ))
+}
 
-        let msg = InstantiateMsg {};
-        let info = mock_info("creator", &coins(1000, "orai"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+pub fn execute_add_oracle(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _address: String,
+    _project_id: Option<String>,
+) -> Result<Response, ContractError> {
+    assert_admin(_deps.storage, &_info.sender)?;
+    let oracle = _deps.api.addr_validate(&_address)?;
+    let mut event =
+        neariot_event(ACTION_ADD_ORACLE, &_info.sender).add_attribute(ATTR_NEW_VALUE, _address);
+    let mut oracles = match &_project_id {
+        Some(project_id) => {
+            let projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+            if !projects.iter().any(|project| &project.id == project_id) {
+                return Err(ContractError::ProjectNotFound {});
+            }
+            event = event.add_attribute(ATTR_PROJECT_ID, project_id);
+            PROJECT_ORACLES.may_load(_deps.storage, project_id)?
+        }
+        None => GLOBAL_ORACLES.may_load(_deps.storage)?,
+    }
+    .unwrap_or_default();
+    if !oracles.contains(&oracle) {
+        oracles.push(oracle);
+    }
+    match &_project_id {
+        Some(project_id) => PROJECT_ORACLES.save(_deps.storage, project_id, &oracles)?,
+        None => GLOBAL_ORACLES.save(_deps.storage, &oracles)?,
+    }
+    Ok(Response::new()
+        .add_attribute("action", ACTION_ADD_ORACLE)
+        .add_event(event))
+}
 
-        let msg = ExecuteMsg::RegisterUser {};
-        let info = mock_info("ciuz", &coins(1000, "orai"));
-        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+pub fn execute_remove_oracle(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _address: String,
+    _project_id: Option<String>,
+) -> Result<Response, ContractError> {
+    assert_admin(_deps.storage, &_info.sender)?;
+    let mut event = neariot_event(ACTION_REMOVE_ORACLE, &_info.sender)
+        .add_attribute(ATTR_OLD_VALUE, _address.clone());
+    match &_project_id {
+        Some(project_id) => {
+            let mut oracles = PROJECT_ORACLES
+                .may_load(_deps.storage, project_id)?
+                .unwrap_or_default();
+            oracles.retain(|oracle| oracle.as_str() != _address);
+            PROJECT_ORACLES.save(_deps.storage, project_id, &oracles)?;
+            event = event.add_attribute(ATTR_PROJECT_ID, project_id);
+        }
+        None => {
+            let mut oracles = GLOBAL_ORACLES.may_load(_deps.storage)?.unwrap_or_default();
+            oracles.retain(|oracle| oracle.as_str() != _address);
+            GLOBAL_ORACLES.save(_deps.storage, &oracles)?;
+        }
+    }
+    Ok(Response::new()
+        .add_attribute("action", ACTION_REMOVE_ORACLE)
+        .add_event(event))
+}
 
-        let msg = QueryMsg::GetUser {
-            id: Addr::unchecked("ciuz"),
-        };
-        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
-        let user: User = from_binary(&res).unwrap();
-        assert_eq!(user.total_spent, Uint128::zero());
-        assert_eq!(user.project_funded.len(), 0);
-        assert_eq!(user.project_watched.len(), 0);
-        assert_eq!(user.project_owned.len(), 0);
+/// Active devices of the project whose latest attestation landed at or after `after`.
+fn attested_devices(storage: &dyn Storage, project_id: &str, after: Timestamp) -> StdResult<u32> {
+    let mut count = 0;
+    for attestation in ATTESTATIONS
+        .prefix(project_id)
+        .range(storage, None, None, Order::Ascending)
+    {
+        let (device_id, attestation) = attestation?;
+        let active = DEVICES
+            .may_load(storage, (project_id, &device_id))?
+            .map_or(false, |device| device.status == DeviceStatus::Active);
+        if active && attestation.submitted_at >= after {
+            count += 1;
+        }
     }
+    Ok(count)
+}
 
-    #[test]
-    fn create_project() {
-        let mut deps = mock_dependencies();
+#[allow(clippy::too_many_arguments)]
+pub fn execute_add_stretch_goal(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _threshold: Uint128,
+    _offer_ids: Vec<String>,
+    _metadata: String,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Editor) {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_status(&_env, project, EDITABLE)?;
+    for offer_id in _offer_ids.iter() {
+        let offer = project
+            .offers
+            .iter_mut()
+            .find(|offer| &offer.id == offer_id)
+            .ok_or(ContractError::OfferNotFound {})?;
+        offer.active = false;
+    }
+    let goal_id = next_id(_deps.storage)?;
+    project.stretch_goals.push(StretchGoal {
+        id: goal_id.clone(),
+        threshold: _threshold,
+        offer_ids: _offer_ids,
+        metadata: _metadata,
+        reached_at: None,
+    });
+    // the goal may already be met
+    let reached = apply_stretch_goals(project, &_env, &_info.sender);
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_ADD_STRETCH_GOAL)
+        .add_event(
+            neariot_event(ACTION_ADD_STRETCH_GOAL, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_AMOUNT, _threshold)
+                .add_attribute(ATTR_STRETCH_GOAL_ID, goal_id),
+        )
+        .add_events(reached))
+}
 
-        let msg = InstantiateMsg {};
-        let info = mock_info("creator", &coins(1000, "orai"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+/// Removing a goal that wasn't reached yet makes its offers available again,
+/// unless another unmet goal still gates them.
+pub fn execute_remove_stretch_goal(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _goal_id: String,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Editor) {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_status(&_env, project, EDITABLE)?;
+    let position = project
+        .stretch_goals
+        .iter()
+        .position(|goal| goal.id == _goal_id)
+        .ok_or(ContractError::StretchGoalNotFound {})?;
+    let goal = project.stretch_goals.remove(position);
+    release_offers(project, &goal.offer_ids);
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_REMOVE_STRETCH_GOAL)
+        .add_event(
+            neariot_event(ACTION_REMOVE_STRETCH_GOAL, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_STRETCH_GOAL_ID, _goal_id),
+        ))
+}
 
-        let msg = ExecuteMsg::RegisterUser {};
-        let info = mock_info("ciuz", &coins(1000, "orai"));
-        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+/// Marks stretch goals crossed by `total_pledged` as reached, activating their
+/// offers and attaching their metadata to existing purchases. Reached goals
+/// stay reached if pledges are later cancelled.
+fn apply_stretch_goals(project: &mut Project, env: &Env, actor: &Addr) -> Vec<Event> {
+    let mut events = vec![];
+    let mut offer_ids = vec![];
+    for goal in project.stretch_goals.iter_mut() {
+        if goal.reached_at.is_some() || project.total_pledged < goal.threshold {
+            continue;
+        }
+        goal.reached_at = Some(env.block.time);
+        offer_ids.extend(goal.offer_ids.iter().cloned());
+        project
+            .bougth_offers
+            .iter_mut()
+            .filter(|offer| !offer.extras.contains(&goal.metadata))
+            .for_each(|offer| offer.extras.push(goal.metadata.clone()));
+        events.push(
+            neariot_event(ACTION_STRETCH_GOAL_REACHED, actor)
+                .add_attribute(ATTR_PROJECT_ID, project.id.clone())
+                .add_attribute(ATTR_AMOUNT, goal.threshold)
+                .add_attribute(ATTR_STRETCH_GOAL_ID, goal.id.clone()),
+        );
+    }
+    release_offers(project, &offer_ids);
+    events
+}
 
-        let msg = ExecuteMsg::CreateProject {
-            metadata: "example".to_string(),
-        };
-        let info = mock_info("ciuz", &coins(1000, "orai"));
-        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
-        let project_id = res.attributes[1].clone().value;
+/// Activates the given offers that no unmet stretch goal lists anymore.
+fn release_offers(project: &mut Project, offer_ids: &[String]) {
+    let gated = project
+        .stretch_goals
+        .iter()
+        .filter(|goal| goal.reached_at.is_none())
+        .flat_map(|goal| goal.offer_ids.iter().cloned())
+        .collect::<Vec<_>>();
+    project
+        .offers
+        .iter_mut()
+        .filter(|offer| offer_ids.contains(&offer.id) && !gated.contains(&offer.id))
+        .for_each(|offer| offer.active = true);
+}
 
-        let msg = QueryMsg::GetProject { id: project_id };
-        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
-        let project: Project = from_binary(&res).unwrap();
-        assert_eq!(project.metadata, "example".to_string());
+pub fn execute_update_min_pledge(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _min_pledge: Uint128,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Editor) {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_status(&_env, project, EDITABLE)?;
+    let event = neariot_event(ACTION_UPDATE_MIN_PLEDGE, &_info.sender)
+        .add_attribute(ATTR_PROJECT_ID, _id)
+        .add_attribute(ATTR_OLD_VALUE, project.min_pledge)
+        .add_attribute(ATTR_NEW_VALUE, _min_pledge);
+    project.min_pledge = _min_pledge;
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_UPDATE_MIN_PLEDGE)
+        .add_event(event))
+}
+
+pub fn execute_pledge(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+) -> Result<Response, ContractError> {
+    assert_can_buy(_deps.storage, &_info.sender)?;
+    let payment = one_coin(&_info)?;
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    assert_status(&_env, project, &[ProjectStatus::Live])?;
+    if project.under_review {
+        return Err(ContractError::ProjectUnderReview {});
+    }
+    assert_funding_denom(project, &payment.denom)?;
+    if payment.amount < project.min_pledge {
+        return Err(ContractError::PledgeTooLow {
+            min: project.min_pledge,
+        });
+    }
+    let pledge = Pledge {
+        id: next_id(_deps.storage)?,
+        amount: payment.amount,
+        denom: payment.denom.clone(),
+        backer: _info.sender.clone(),
+        create_at: _env.block.time,
+    };
+    let event = neariot_event(ACTION_PLEDGE, &_info.sender)
+        .add_attribute(ATTR_PROJECT_ID, _id.clone())
+        .add_attribute(ATTR_PURCHASE_ID, pledge.id.clone())
+        .add_attribute(ATTR_AMOUNT, payment.amount)
+        .add_attribute(ATTR_DENOM, payment.denom.clone())
+        .add_attribute(ATTR_OLD_VALUE, project.total_pledged)
+        .add_attribute(ATTR_NEW_VALUE, project.total_pledged + payment.amount);
+    project.total_pledged += payment.amount;
+    project.pledges.push(pledge);
+    let reached = apply_stretch_goals(project, &_env, &_info.sender);
+    update_reputation(_deps.storage, &project.owner, |reputation| {
+        reputation.backings += 1
+    })?;
+    record_contribution(_deps.storage, &_env, &_id, &_info.sender, payment)?;
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_PLEDGE)
+        .add_event(event)
+        .add_events(reached))
+}
+
+pub fn execute_update_cancellation_policy(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _cutoff: Option<u64>,
+    _fee: Decimal,
+) -> Result<Response, ContractError> {
+    if _fee > Decimal::one() {
+        return Err(ContractError::InvalidFee {});
+    }
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Editor) {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_status(&_env, project, EDITABLE)?;
+    let event = neariot_event(ACTION_UPDATE_CANCELLATION_POLICY, &_info.sender)
+        .add_attribute(ATTR_PROJECT_ID, _id)
+        .add_attribute(ATTR_OLD_VALUE, project.cancellation.fee.to_string())
+        .add_attribute(ATTR_NEW_VALUE, _fee.to_string());
+    project.cancellation = CancellationPolicy {
+        cutoff: _cutoff.map(Timestamp::from_seconds),
+        fee: _fee,
+    };
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_UPDATE_CANCELLATION_POLICY)
+        .add_event(event))
+}
+
+pub fn execute_cancel_purchase(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _purchase_id: String,
+    _amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    project.status = project_status(&_env, project);
+    if project.status == ProjectStatus::Cancelled {
+        return Err(ContractError::ProjectCancelled {});
+    }
+    if OPEN_DISPUTES.has(_deps.storage, (&_id, &_purchase_id)) {
+        return Err(ContractError::DisputeAlreadyOpen {});
+    }
+    if project.status != ProjectStatus::Live
+        || project
+            .cancellation
+            .cutoff
+            .map_or(false, |cutoff| _env.block.time >= cutoff)
+    {
+        return Err(ContractError::CancellationClosed {});
+    }
+
+    // a reduced purchase must still cover what its offer cost, a reduced pledge
+    // the minimum
+    let purchase_index = project
+        .bougth_offers
+        .iter()
+        .position(|offer| offer.id == _purchase_id && offer.buyer == _info.sender);
+    let pledge_index = project
+        .pledges
+        .iter()
+        .position(|pledge| pledge.id == _purchase_id && pledge.backer == _info.sender);
+    let (paid, denom, min_left, referrer) = if let Some(index) = purchase_index {
+        let bougth_offer = &project.bougth_offers[index];
+        let referrer = bougth_offer
+            .referral
+            .as_ref()
+            .map(|referral| referral.referrer.clone());
+        (
+            bougth_offer.price,
+            bougth_offer.denom.clone(),
+            bougth_offer.offer_price,
+            referrer,
+        )
+    } else if let Some(index) = pledge_index {
+        let pledge = &project.pledges[index];
+        (
+            pledge.amount,
+            pledge.denom.clone(),
+            project.min_pledge,
+            None,
+        )
+    } else {
+        return Err(ContractError::PurchaseNotFound {});
+    };
+    let amount = _amount.unwrap_or(paid);
+    if amount.is_zero() || amount > paid {
+        return Err(ContractError::InvalidAmount {});
+    }
+    let remaining = paid - amount;
+    if !remaining.is_zero() && remaining < min_left {
+        return Err(ContractError::InvalidAmount {});
+    }
+    let fee = amount * project.cancellation.fee;
+    let refund = amount - fee;
+
+    match (purchase_index, pledge_index) {
+        (Some(index), _) if remaining.is_zero() => {
+            project.bougth_offers.remove(index);
+        }
+        (Some(index), _) => project.bougth_offers[index].price = remaining,
+        (None, Some(index)) if remaining.is_zero() => {
+            project.pledges.remove(index);
+        }
+        (None, Some(index)) => project.pledges[index].amount = remaining,
+        (None, None) => unreachable!(),
+    }
+    if !fee.is_zero() {
+        let mut cancel_fees = NativeBalance(project.cancel_fees.clone());
+        cancel_fees += coin(fee.u128(), &denom);
+        project.cancel_fees = cancel_fees.into_vec();
+    }
+    // the fee stays pledged to the project
+    project.total_pledged = project.total_pledged.saturating_sub(refund);
+    // a reduction keeps the backing, only a full cancel counts as a refund
+    if remaining.is_zero() {
+        update_reputation(_deps.storage, &project.owner, |reputation| {
+            reputation.refunds += 1
+        })?;
+    }
+    PROJECTS.save(_deps.storage, &projects)?;
+    deduct_contribution(
+        _deps.storage,
+        &_id,
+        &_info.sender,
+        coin(refund.u128(), &denom),
+        remaining.is_zero(),
+    )?;
+    if let Some(referrer) = referrer {
+        let mut stats = REFERRAL_STATS
+            .may_load(_deps.storage, &referrer)?
+            .unwrap_or_default();
+        stats.volume = saturating_sub(
+            &NativeBalance(stats.volume),
+            &NativeBalance(vec![coin(amount.u128(), &denom)]),
+        )
+        .into_vec();
+        if remaining.is_zero() {
+            stats.referrals = stats.referrals.saturating_sub(1);
+        }
+        REFERRAL_STATS.save(_deps.storage, &referrer, &stats)?;
+    }
+
+    let event = neariot_event(ACTION_CANCEL_PURCHASE, &_info.sender)
+        .add_attribute(ATTR_PROJECT_ID, _id)
+        .add_attribute(ATTR_PURCHASE_ID, _purchase_id)
+        .add_attribute(ATTR_AMOUNT, refund)
+        .add_attribute(ATTR_DENOM, denom.clone())
+        .add_attribute(ATTR_OLD_VALUE, paid)
+        .add_attribute(ATTR_NEW_VALUE, remaining);
+    let mut res = Response::new()
+        .add_attribute("action", ACTION_CANCEL_PURCHASE)
+        .add_event(event);
+    if !refund.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: _info.sender.to_string(),
+            amount: vec![coin(refund.u128(), denom)],
+        });
+    }
+    Ok(res)
+}
+
+pub fn execute_update_subscription_plan(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _period: u64,
+    _min_price: Uint128,
+) -> Result<Response, ContractError> {
+    if _period == 0 {
+        return Err(ContractError::InvalidAmount {});
+    }
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Editor) {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_status(&_env, project, OPEN)?;
+    project.subscription_plan = Some(SubscriptionPlan {
+        period: _period,
+        min_price: _min_price,
+    });
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_UPDATE_SUBSCRIPTION_PLAN)
+        .add_event(
+            neariot_event(ACTION_UPDATE_SUBSCRIPTION_PLAN, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_AMOUNT, _min_price)
+                .add_attribute(ATTR_NEW_VALUE, _period.to_string()),
+        ))
+}
+
+pub fn execute_subscribe(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _periods: u32,
+) -> Result<Response, ContractError> {
+    assert_can_buy(_deps.storage, &_info.sender)?;
+    let payment = one_coin(&_info)?;
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    assert_status(
+        &_env,
+        project,
+        &[
+            ProjectStatus::Live,
+            ProjectStatus::Funded,
+            ProjectStatus::Delivering,
+        ],
+    )?;
+    if project.under_review {
+        return Err(ContractError::ProjectUnderReview {});
+    }
+    let plan = project
+        .subscription_plan
+        .as_ref()
+        .ok_or(ContractError::NoSubscriptionPlan {})?;
+    if _periods == 0 || !(payment.amount % Uint128::from(_periods)).is_zero() {
+        return Err(ContractError::InvalidAmount {});
+    }
+    let amount_per_period = payment.amount / Uint128::from(_periods);
+    if amount_per_period < plan.min_price {
+        return Err(ContractError::InsufficientFunds {});
+    }
+    let subscription = Subscription {
+        id: next_id(_deps.storage)?,
+        project_id: _id.clone(),
+        backer: _info.sender.clone(),
+        amount_per_period,
+        denom: payment.denom.clone(),
+        period: plan.period,
+        periods: _periods,
+        claimed: 0,
+        start_at: _env.block.time,
+        cancelled: false,
+    };
+    SUBSCRIPTIONS.save(
+        _deps.storage,
+        (_id.as_str(), subscription.id.as_str()),
+        &subscription,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_SUBSCRIBE)
+        .add_event(
+            neariot_event(ACTION_SUBSCRIBE, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_PURCHASE_ID, subscription.id)
+                .add_attribute(ATTR_AMOUNT, payment.amount)
+                .add_attribute(ATTR_DENOM, payment.denom)
+                .add_attribute(ATTR_NEW_VALUE, _periods.to_string()),
+        ))
+}
+
+/// Pays the owner for the next period that has come due.
+pub fn execute_claim_subscription(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _subscription_id: String,
+) -> Result<Response, ContractError> {
+    let projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Treasurer) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if project.status == ProjectStatus::Cancelled {
+        return Err(ContractError::ProjectCancelled {});
+    }
+    let key = (_id.as_str(), _subscription_id.as_str());
+    let mut subscription = SUBSCRIPTIONS
+        .may_load(_deps.storage, key)?
+        .ok_or(ContractError::SubscriptionNotFound {})?;
+    if subscription.claimed >= subscription.due_periods(_env.block.time) {
+        return Err(ContractError::NothingToClaim {});
+    }
+    subscription.claimed += 1;
+    SUBSCRIPTIONS.save(_deps.storage, key, &subscription)?;
+    // a treasurer may claim, the owner is always paid
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: project.owner.to_string(),
+            amount: vec![coin(
+                subscription.amount_per_period.u128(),
+                &subscription.denom,
+            )],
+        })
+        .add_attribute("action", ACTION_CLAIM_SUBSCRIPTION)
+        .add_event(
+            neariot_event(ACTION_CLAIM_SUBSCRIPTION, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_PURCHASE_ID, _subscription_id)
+                .add_attribute(ATTR_AMOUNT, subscription.amount_per_period)
+                .add_attribute(ATTR_DENOM, subscription.denom)
+                .add_attribute(ATTR_NEW_VALUE, subscription.claimed.to_string()),
+        ))
+}
+
+/// Refunds the periods that haven't come due yet. Due periods stay claimable
+/// by the owner, unless the project was cancelled.
+pub fn execute_cancel_subscription(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _subscription_id: String,
+) -> Result<Response, ContractError> {
+    let projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    let key = (_id.as_str(), _subscription_id.as_str());
+    let mut subscription = SUBSCRIPTIONS
+        .may_load(_deps.storage, key)?
+        .ok_or(ContractError::SubscriptionNotFound {})?;
+    if subscription.backer != _info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let kept = if project.status == ProjectStatus::Cancelled {
+        subscription.claimed
+    } else {
+        subscription.due_periods(_env.block.time)
+    };
+    let refunded = subscription.periods - kept;
+    if refunded == 0 {
+        return Err(ContractError::NothingToClaim {});
+    }
+    subscription.periods = kept;
+    subscription.cancelled = true;
+    SUBSCRIPTIONS.save(_deps.storage, key, &subscription)?;
+    let refund = subscription.amount_per_period * Uint128::from(refunded);
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: _info.sender.to_string(),
+            amount: vec![coin(refund.u128(), &subscription.denom)],
+        })
+        .add_attribute("action", ACTION_CANCEL_SUBSCRIPTION)
+        .add_event(
+            neariot_event(ACTION_CANCEL_SUBSCRIPTION, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_PURCHASE_ID, _subscription_id)
+                .add_attribute(ATTR_AMOUNT, refund)
+                .add_attribute(ATTR_DENOM, subscription.denom)
+                .add_attribute(ATTR_OLD_VALUE, (kept + refunded).to_string())
+                .add_attribute(ATTR_NEW_VALUE, kept.to_string()),
+        ))
+}
+
+/// Adds a payment to the backer's ledger entry for the project and to their
+/// `User` totals.
+pub fn record_contribution(
+    storage: &mut dyn Storage,
+    env: &Env,
+    project_id: &str,
+    backer: &Addr,
+    payment: Coin,
+) -> StdResult<()> {
+    let mut contribution = CONTRIBUTIONS
+        .may_load(storage, (project_id, backer))?
+        .unwrap_or(Contribution {
+            project_id: project_id.to_string(),
+            backer: backer.clone(),
+            amount: vec![],
+            purchase_count: 0,
+            first_at: env.block.time,
+            last_at: env.block.time,
+        });
+    let mut amount = NativeBalance(contribution.amount);
+    amount += payment.clone();
+    contribution.amount = amount.into_vec();
+    contribution.purchase_count += 1;
+    contribution.last_at = env.block.time;
+    CONTRIBUTIONS.save(storage, (project_id, backer), &contribution)?;
+    BACKED_PROJECTS.save(storage, (backer, project_id), &Empty {})?;
+
+    let mut users = USERS.may_load(storage)?.unwrap_or_default();
+    if let Some(user) = users.iter_mut().find(|user| &user.address == backer) {
+        user.total_spent += payment.amount;
+        if !user
+            .project_funded
+            .iter()
+            .any(|funded| funded == project_id)
+        {
+            user.project_funded.push(project_id.to_string());
+        }
+        USERS.save(storage, &users)?;
+    }
+    Ok(())
+}
+
+/// Takes a refund back out of the backer's ledger entry and `User` totals.
+/// `cancelled` is set when a whole purchase or pledge went away.
+pub fn deduct_contribution(
+    storage: &mut dyn Storage,
+    project_id: &str,
+    backer: &Addr,
+    refund: Coin,
+    cancelled: bool,
+) -> StdResult<()> {
+    let mut contribution = CONTRIBUTIONS.load(storage, (project_id, backer))?;
+    contribution.amount = saturating_sub(
+        &NativeBalance(contribution.amount),
+        &NativeBalance(vec![refund.clone()]),
+    )
+    .into_vec();
+    if cancelled {
+        contribution.purchase_count = contribution.purchase_count.saturating_sub(1);
+    }
+    let withdrawn = contribution.amount.is_empty();
+    if withdrawn {
+        CONTRIBUTIONS.remove(storage, (project_id, backer));
+        BACKED_PROJECTS.remove(storage, (backer, project_id));
+    } else {
+        CONTRIBUTIONS.save(storage, (project_id, backer), &contribution)?;
+    }
+
+    let mut users = USERS.may_load(storage)?.unwrap_or_default();
+    if let Some(user) = users.iter_mut().find(|user| &user.address == backer) {
+        user.total_spent = user.total_spent.saturating_sub(refund.amount);
+        if withdrawn {
+            user.project_funded.retain(|funded| funded != project_id);
+        }
+        USERS.save(storage, &users)?;
+    }
+    Ok(())
+}
+
+pub fn must_pay_funds(balance: &NativeBalance, denom: &str) -> Result<Uint128, ContractError> {
+    match balance.0.len() {
+        0 => Err(ContractError::NoFunds {}),
+        1 => {
+            let balance = &balance.0;
+            let payment = balance[0].amount;
+            if balance[0].denom == denom {
+                Ok(payment)
+            } else {
+                Err(ContractError::MissingDenom(denom.to_string()))
+            }
+        }
+        _ => Err(ContractError::ExtraDenoms(denom.to_string())),
+    }
+}
+
+pub fn execute_rate_project_offer(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _offer_id: String,
+    _rate: Uint128,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    if !projects.iter().any(|project| project.id == _id) {
+        return Err(ContractError::ProjectNotFound {});
+    }
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .unwrap();
+    // only the buyer rates a purchase, ratings feed the owner's reputation
+    let purchase = project
+        .bougth_offers
+        .iter_mut()
+        .find(|offer| offer.id == _offer_id && offer.buyer == _info.sender)
+        .ok_or(ContractError::PurchaseNotFound {})?;
+    let old_rate = purchase.rate;
+    purchase.rate = _rate;
+    update_reputation(_deps.storage, &project.owner, |reputation| {
+        reputation.rating_sum = reputation.rating_sum.saturating_sub(old_rate) + _rate;
+        if old_rate.is_zero() && !_rate.is_zero() {
+            reputation.rating_count += 1;
+        } else if !old_rate.is_zero() && _rate.is_zero() {
+            reputation.rating_count = reputation.rating_count.saturating_sub(1);
+        }
+    })?;
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_RATE_OFFER)
+        .add_event(
+            neariot_event(ACTION_RATE_OFFER, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_PURCHASE_ID, _offer_id)
+                .add_attribute(ATTR_NEW_VALUE, _rate),
+        ))
+}
+
+pub fn execute_watch_project(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    if !projects.iter().any(|project| project.id == _id) {
+        return Err(ContractError::ProjectNotFound {});
+    }
+    projects.iter_mut().for_each(|project| {
+        if project.id == _id {
+            project.watchers.iter().for_each(|watcher| {
+                if watcher.eq(&_info.sender.clone()) {
+                    project.clone().watchers.push(_info.sender.clone());
+                }
+            });
+        }
+    });
+    // let mut user = USERS.load(_deps.storage)?;
+    // user.project_watched.iter().for_each(|project|{
+    //     if project != &_id{
+    //         user.project_watched.push(_id);
+    //     }
+    // });
+    let mut users = USERS.load(_deps.storage).unwrap_or_default();
+    users.iter_mut().for_each(|user| {
+        if user.address == _info.sender {
+            user.clone().project_watched.iter().for_each(|project| {
+                if !project.clone().eq(&_id.clone()) {
+                    user.project_watched.push(_id.clone());
+                }
+            });
+        }
+    });
+    USERS.save(_deps.storage, &users)?;
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_WATCH_PROJECT)
+        .add_event(
+            neariot_event(ACTION_WATCH_PROJECT, &_info.sender).add_attribute(ATTR_PROJECT_ID, _id),
+        ))
+}
+
+pub fn execute_unwatch_project(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    if !projects.iter().any(|project| project.id == _id) {
+        return Err(ContractError::ProjectNotFound {});
+    }
+    projects.iter_mut().for_each(|project| {
+        if project.id == _id {
+            project.watchers.retain(|watcher| watcher != &_info.sender);
+        }
+    });
+    // let mut user = USERS.load(_deps.storage)?;
+    // user.project_watched.retain(|project| project != &_id);
+    let mut users = USERS.load(_deps.storage).unwrap_or_default();
+    users.iter_mut().for_each(|user| {
+        if user.address == _info.sender {
+            user.project_watched.retain(|project| project != &_id);
+        }
+    });
+    USERS.save(_deps.storage, &users)?;
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_UNWATCH_PROJECT)
+        .add_event(
+            neariot_event(ACTION_UNWATCH_PROJECT, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id),
+        ))
+}
+
+pub fn execute_rate_project(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _rate: Uint128,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    if !projects.iter().any(|project| project.id == _id) {
+        return Err(ContractError::ProjectNotFound {});
+    }
+    let project = projects.iter().find(|project| project.id == _id).unwrap();
+    // the average is taken over purchases, there's nothing to rate without one
+    if project.bougth_offers.is_empty() {
+        return Err(ContractError::PurchaseNotFound {});
+    }
+    let old_rate = project.avg_rate;
+    projects.iter_mut().for_each(|project| {
+        if project.id == _id {
+            project.avg_rate = project
+                .bougth_offers
+                .iter()
+                .fold(Uint128::zero(), |acc, offer| acc + offer.rate)
+                / Uint128::from(project.bougth_offers.len() as u128);
+        }
+    });
+    PROJECTS.save(_deps.storage, &projects)?;
+    let new_rate = projects
+        .iter()
+        .find(|project| project.id == _id)
+        .unwrap()
+        .avg_rate;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_RATE_PROJECT)
+        .add_event(
+            neariot_event(ACTION_RATE_PROJECT, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _id)
+                .add_attribute(ATTR_OLD_VALUE, old_rate)
+                .add_attribute(ATTR_NEW_VALUE, new_rate),
+        ))
+}
+
+pub fn execute_pause(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _operation: Option<PauseOperation>,
+) -> Result<Response, ContractError> {
+    assert_admin(_deps.storage, &_info.sender)?;
+    let event = neariot_event(ACTION_PAUSE, &_info.sender)
+        .add_attribute(ATTR_NEW_VALUE, pause_scope(&_operation));
+    let mut pause = PAUSE.may_load(_deps.storage)?.unwrap_or_default();
+    match _operation {
+        None => pause.global = true,
+        Some(operation) => {
+            if !pause.operations.contains(&operation) {
+                pause.operations.push(operation);
+            }
+        }
+    }
+    PAUSE.save(_deps.storage, &pause)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_PAUSE)
+        .add_event(event))
+}
+
+pub fn execute_unpause(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _operation: Option<PauseOperation>,
+) -> Result<Response, ContractError> {
+    assert_admin(_deps.storage, &_info.sender)?;
+    let event = neariot_event(ACTION_UNPAUSE, &_info.sender)
+        .add_attribute(ATTR_NEW_VALUE, pause_scope(&_operation));
+    let mut pause = PAUSE.may_load(_deps.storage)?.unwrap_or_default();
+    match _operation {
+        None => pause.global = false,
+        Some(operation) => pause.operations.retain(|paused| paused != &operation),
+    }
+    PAUSE.save(_deps.storage, &pause)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_UNPAUSE)
+        .add_event(event))
+}
+
+fn pause_scope(operation: &Option<PauseOperation>) -> String {
+    match operation {
+        Some(operation) => operation.as_str().to_string(),
+        None => "global".to_string(),
+    }
+}
+
+pub fn execute_moderate_project(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _action: ModerationAction,
+    _reason: String,
+) -> Result<Response, ContractError> {
+    assert_admin(_deps.storage, &_info.sender)?;
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    let mut events = vec![];
+    match _action {
+        ModerationAction::Hide => project.hidden = true,
+        ModerationAction::Unhide => project.hidden = false,
+        ModerationAction::Flag => project.under_review = true,
+        ModerationAction::Unflag => project.under_review = false,
+        ModerationAction::Cancel => {
+            project.status = project_status(&_env, project);
+            // only what the owner hasn't withdrawn is left to give back
+            let escrow = project_escrow(project);
+            transition(project, ProjectStatus::Cancelled)?;
+            let contributions = CONTRIBUTIONS
+                .prefix(_id.as_str())
+                .range(_deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            let mut paid_in = NativeBalance::default();
+            for (_, contribution) in contributions.iter() {
+                for paid in contribution.amount.iter() {
+                    paid_in += paid.clone();
+                }
+            }
+            // backers share it pro rata, in full if nothing was withdrawn
+            for (backer, contribution) in contributions {
+                for paid in contribution.amount {
+                    let total = balance_of(&paid_in, &paid.denom);
+                    let left = balance_of(&escrow, &paid.denom).min(total);
+                    if left.is_zero() {
+                        continue;
+                    }
+                    let refund = coin(paid.amount.multiply_ratio(left, total).u128(), paid.denom);
+                    if refund.amount.is_zero() {
+                        continue;
+                    }
+                    credit_refund(_deps.storage, &backer, refund.clone())?;
+                    events.push(
+                        neariot_event(ACTION_CREDIT_REFUND, &backer)
+                            .add_attribute(ATTR_PROJECT_ID, _id.clone())
+                            .add_attribute(ATTR_AMOUNT, refund.amount)
+                            .add_attribute(ATTR_DENOM, refund.denom),
+                    );
+                }
+            }
+            // disputed purchases were refunded with everything else
+            let open_disputes = OPEN_DISPUTES
+                .prefix(_id.as_str())
+                .range(_deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            for (purchase_id, dispute_id) in open_disputes {
+                let mut dispute = DISPUTES.load(_deps.storage, &dispute_id)?;
+                dispute.status = DisputeStatus::Closed;
+                dispute.resolver = Some(_info.sender.clone());
+                dispute.resolved_at = Some(_env.block.time);
+                DISPUTES.save(_deps.storage, &dispute_id, &dispute)?;
+                OPEN_DISPUTES.remove(_deps.storage, (&_id, &purchase_id));
+            }
+            project.frozen = vec![];
+            project.total_pledged = Uint128::zero();
+            update_reputation(_deps.storage, &project.owner, |reputation| {
+                reputation.failed_campaigns += 1
+            })?;
+        }
+    }
+    PROJECTS.save(_deps.storage, &projects)?;
+
+    let event = neariot_event(ACTION_MODERATE_PROJECT, &_info.sender)
+        .add_attribute(ATTR_PROJECT_ID, _id.clone())
+        .add_attribute(ATTR_NEW_VALUE, _action.as_str());
+    let mut log = MODERATION_LOG.may_load(_deps.storage)?.unwrap_or_default();
+    log.push(ModerationRecord {
+        project_id: _id,
+        action: _action,
+        reason: _reason,
+        actor: _info.sender,
+        create_at: _env.block.time,
+    });
+    MODERATION_LOG.save(_deps.storage, &log)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_MODERATE_PROJECT)
+        .add_event(event)
+        .add_events(events))
+}
+
+pub fn execute_claim_refund(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let refunds = REFUNDS
+        .may_load(_deps.storage, &_info.sender)?
+        .unwrap_or_default();
+    if refunds.is_empty() {
+        return Err(ContractError::NoFunds {});
+    }
+    REFUNDS.remove(_deps.storage, &_info.sender);
+    let mut event = neariot_event(ACTION_CLAIM_REFUND, &_info.sender);
+    for refund in refunds.iter() {
+        event = event
+            .add_attribute(ATTR_AMOUNT, refund.amount)
+            .add_attribute(ATTR_DENOM, refund.denom.clone());
+    }
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: _info.sender.to_string(),
+            amount: refunds,
+        })
+        .add_attribute("action", ACTION_CLAIM_REFUND)
+        .add_event(event))
+}
+
+pub fn execute_withdraw(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Treasurer) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let owner = project.owner.clone();
+    project.status = project_status(&_env, project);
+    if !matches!(
+        project.status,
+        ProjectStatus::Funded | ProjectStatus::Delivering | ProjectStatus::Completed
+    ) {
+        return Err(ContractError::GoalNotReached {});
+    }
+    let available = project_withdrawable(project);
+    if available.is_empty() {
+        return Err(ContractError::NoFunds {});
+    }
+
+    // referral rewards come out of the owner's proceeds
+    let mut rewards = NativeBalance::default();
+    let mut events = vec![];
+    for (index, referrer, reward) in payable_referral_rewards(project, &available) {
+        rewards += reward.clone();
+        credit_referral_reward(_deps.storage, &referrer, reward.clone())?;
+        if let Some(referral) = project.bougth_offers[index].referral.as_mut() {
+            referral.settled = true;
+        }
+        events.push(
+            neariot_event(ACTION_CREDIT_REFERRAL_REWARD, &referrer)
+                .add_attribute(ATTR_PROJECT_ID, _id.clone())
+                .add_attribute(ATTR_AMOUNT, reward.amount)
+                .add_attribute(ATTR_DENOM, reward.denom),
+        );
+    }
+
+    let amount = saturating_sub(&available, &rewards).into_vec();
+    let mut withdrawn = NativeBalance(project.withdrawn.clone());
+    for paid in amount.iter().chain(rewards.into_vec().iter()) {
+        withdrawn += paid.clone();
+    }
+    let mut event =
+        neariot_event(ACTION_WITHDRAW, &_info.sender).add_attribute(ATTR_PROJECT_ID, _id);
+    for payout in amount.iter() {
+        event = event
+            .add_attribute(ATTR_AMOUNT, payout.amount)
+            .add_attribute(ATTR_DENOM, payout.denom.clone());
+    }
+    project.withdrawn = withdrawn.into_vec();
+    PROJECTS.save(_deps.storage, &projects)?;
+    let mut res = Response::new()
+        .add_attribute("action", ACTION_WITHDRAW)
+        .add_event(event)
+        .add_events(events);
+    // a treasurer may withdraw, the owner is always paid
+    if !amount.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: owner.to_string(),
+            amount,
+        });
+    }
+    Ok(res)
+}
+
+/// `None` restricts the action to the owner, who also holds every role.
+fn assert_role(
+    projects: &[Project],
+    project_id: &str,
+    sender: &Addr,
+    role: Option<Role>,
+) -> Result<(), ContractError> {
+    let project = projects
+        .iter()
+        .find(|project| project.id == project_id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    let allowed = match role {
+        Some(role) => project.has_role(sender, role),
+        None => &project.owner == sender,
+    };
+    if !allowed {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_register_device(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _project_id: String,
+    _device_id: String,
+    _pubkey: Binary,
+    _key_type: KeyType,
+    _model: String,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    assert_role(&projects, &_project_id, &_info.sender, Some(Role::Reporter))?;
+    assert_status(
+        &_env,
+        projects
+            .iter_mut()
+            .find(|project| project.id == _project_id)
+            .unwrap(),
+        OPEN,
+    )?;
+    if DEVICES.has(_deps.storage, (&_project_id, &_device_id)) {
+        return Err(ContractError::DeviceAlreadyExists {});
+    }
+    if !_key_type.is_valid_key(&_pubkey) {
+        return Err(ContractError::InvalidPublicKey {});
+    }
+    let device = Device {
+        id: _device_id.clone(),
+        project_id: _project_id.clone(),
+        pubkey: _pubkey,
+        key_type: _key_type,
+        model: _model,
+        status: DeviceStatus::Active,
+        registered_at: _env.block.time,
+        updated_at: _env.block.time,
+    };
+    DEVICES.save(_deps.storage, (&_project_id, &_device_id), &device)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_REGISTER_DEVICE)
+        .add_event(
+            neariot_event(ACTION_REGISTER_DEVICE, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _project_id)
+                .add_attribute(ATTR_DEVICE_ID, _device_id)
+                .add_attribute(ATTR_NEW_VALUE, device.pubkey.to_base64()),
+        ))
+}
+
+pub fn execute_rotate_device_key(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _project_id: String,
+    _device_id: String,
+    _pubkey: Binary,
+    _key_type: KeyType,
+) -> Result<Response, ContractError> {
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    assert_role(&projects, &_project_id, &_info.sender, Some(Role::Reporter))?;
+    assert_status(
+        &_env,
+        projects
+            .iter_mut()
+            .find(|project| project.id == _project_id)
+            .unwrap(),
+        OPEN,
+    )?;
+    let mut device = DEVICES
+        .may_load(_deps.storage, (&_project_id, &_device_id))?
+        .ok_or(ContractError::DeviceNotFound {})?;
+    if device.status == DeviceStatus::Retired {
+        return Err(ContractError::DeviceRetired {});
+    }
+    if !_key_type.is_valid_key(&_pubkey) {
+        return Err(ContractError::InvalidPublicKey {});
+    }
+    let event = neariot_event(ACTION_ROTATE_DEVICE_KEY, &_info.sender)
+        .add_attribute(ATTR_PROJECT_ID, _project_id.clone())
+        .add_attribute(ATTR_DEVICE_ID, _device_id.clone())
+        .add_attribute(ATTR_OLD_VALUE, device.pubkey.to_base64())
+        .add_attribute(ATTR_NEW_VALUE, _pubkey.to_base64());
+    device.pubkey = _pubkey;
+    device.key_type = _key_type;
+    device.updated_at = _env.block.time;
+    DEVICES.save(_deps.storage, (&_project_id, &_device_id), &device)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_ROTATE_DEVICE_KEY)
+        .add_event(event))
+}
+
+/// Retired devices stay listed but can no longer be rotated.
+pub fn execute_retire_device(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _project_id: String,
+    _device_id: String,
+) -> Result<Response, ContractError> {
+    let projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    assert_role(&projects, &_project_id, &_info.sender, Some(Role::Reporter))?;
+    let mut device = DEVICES
+        .may_load(_deps.storage, (&_project_id, &_device_id))?
+        .ok_or(ContractError::DeviceNotFound {})?;
+    if device.status == DeviceStatus::Retired {
+        return Err(ContractError::DeviceRetired {});
+    }
+    device.status = DeviceStatus::Retired;
+    device.updated_at = _env.block.time;
+    DEVICES.save(_deps.storage, (&_project_id, &_device_id), &device)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_RETIRE_DEVICE)
+        .add_event(
+            neariot_event(ACTION_RETIRE_DEVICE, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _project_id)
+                .add_attribute(ATTR_DEVICE_ID, _device_id),
+        ))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_submit_telemetry(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _project_id: String,
+    _device_id: String,
+    _payload_hash: Binary,
+    _counter: u64,
+    _signature: Binary,
+) -> Result<Response, ContractError> {
+    let device = DEVICES
+        .may_load(_deps.storage, (&_project_id, &_device_id))?
+        .ok_or(ContractError::DeviceNotFound {})?;
+    if device.status == DeviceStatus::Retired {
+        return Err(ContractError::DeviceRetired {});
+    }
+    // counters are strictly increasing per device, so a signature can't be replayed
+    if let Some(last) = ATTESTATIONS.may_load(_deps.storage, (&_project_id, &_device_id))? {
+        if _counter <= last.counter {
+            return Err(ContractError::StaleCounter { last: last.counter });
+        }
+    }
+    let digest = telemetry_digest(&_project_id, &_device_id, &_payload_hash, _counter);
+    let verified = match device.key_type {
+        KeyType::Secp256k1 => _deps
+            .api
+            .secp256k1_verify(&digest, &_signature, &device.pubkey),
+        KeyType::Ed25519 => _deps
+            .api
+            .ed25519_verify(&digest, &_signature, &device.pubkey),
+    };
+    if !verified.unwrap_or(false) {
+        return Err(ContractError::InvalidSignature {});
+    }
+
+    let attestation = Attestation {
+        project_id: _project_id.clone(),
+        device_id: _device_id.clone(),
+        payload_hash: _payload_hash,
+        counter: _counter,
+        signature: _signature,
+        submitter: _info.sender.clone(),
+        submitted_at: _env.block.time,
+    };
+    ATTESTATIONS.save(_deps.storage, (&_project_id, &_device_id), &attestation)?;
+
+    let mut projects = PROJECTS.load(_deps.storage)?;
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _project_id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    assert_status(&_env, project, OPEN)?;
+    let completed = apply_milestones(_deps.storage, &_env, project, &_info.sender)?;
+    if !completed.is_empty() {
+        PROJECTS.save(_deps.storage, &projects)?;
+    }
+    Ok(Response::new()
+        .add_attribute("action", ACTION_SUBMIT_TELEMETRY)
+        .add_event(
+            neariot_event(ACTION_SUBMIT_TELEMETRY, &_info.sender)
+                .add_attribute(ATTR_PROJECT_ID, _project_id)
+                .add_attribute(ATTR_DEVICE_ID, _device_id)
+                .add_attribute(ATTR_NEW_VALUE, _counter.to_string()),
+        )
+        .add_events(completed))
+}
+
+/// Rewards owed to referrers for purchases not yet settled by a withdrawal.
+fn unsettled_referral_rewards(project: &Project) -> Vec<(Addr, Coin)> {
+    project
+        .bougth_offers
+        .iter()
+        .filter_map(|offer| {
+            let referral = offer
+                .referral
+                .as_ref()
+                .filter(|referral| !referral.settled)?;
+            let reward = offer.price * referral.share;
+            (!reward.is_zero())
+                .then(|| (referral.referrer.clone(), coin(reward.u128(), &offer.denom)))
+        })
+        .collect()
+}
+
+/// Unsettled rewards, by purchase index, that `available` covers in full.
+/// The rest wait for a withdrawal with more funds unlocked.
+fn payable_referral_rewards(
+    project: &Project,
+    available: &NativeBalance,
+) -> Vec<(usize, Addr, Coin)> {
+    let mut budget = available.clone();
+    let mut payable = vec![];
+    for (index, offer) in project.bougth_offers.iter().enumerate() {
+        let referral = match offer.referral.as_ref().filter(|referral| !referral.settled) {
+            Some(referral) => referral,
+            None => continue,
+        };
+        let reward = coin((offer.price * referral.share).u128(), &offer.denom);
+        if reward.amount.is_zero() || balance_of(&budget, &reward.denom) < reward.amount {
+            continue;
+        }
+        budget = saturating_sub(&budget, &NativeBalance(vec![reward.clone()]));
+        payable.push((index, referral.referrer.clone(), reward));
+    }
+    payable
+}
+
+fn credit_referral_reward(
+    storage: &mut dyn Storage,
+    referrer: &Addr,
+    reward: Coin,
+) -> StdResult<()> {
+    let mut balance = NativeBalance(
+        REFERRAL_REWARDS
+            .may_load(storage, referrer)?
+            .unwrap_or_default(),
+    );
+    balance += reward.clone();
+    REFERRAL_REWARDS.save(storage, referrer, &balance.into_vec())?;
+    let mut stats = REFERRAL_STATS
+        .may_load(storage, referrer)?
+        .unwrap_or_default();
+    let mut earned = NativeBalance(stats.earned);
+    earned += reward;
+    stats.earned = earned.into_vec();
+    REFERRAL_STATS.save(storage, referrer, &stats)
+}
+
+pub fn execute_update_referral_share(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _id: String,
+    _share: Decimal,
+) -> Result<Response, ContractError> {
+    if _share > Decimal::one() {
+        return Err(ContractError::InvalidShare {});
+    }
+    let mut projects = PROJECTS.load(_deps.storage).unwrap_or_default();
+    let project = projects
+        .iter_mut()
+        .find(|project| project.id == _id)
+        .ok_or(ContractError::ProjectNotFound {})?;
+    if !project.has_role(&_info.sender, Role::Editor) {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_status(&_env, project, EDITABLE)?;
+    let event = neariot_event(ACTION_UPDATE_REFERRAL_SHARE, &_info.sender)
+        .add_attribute(ATTR_PROJECT_ID, _id)
+        .add_attribute(ATTR_OLD_VALUE, project.referral_share.to_string())
+        .add_attribute(ATTR_NEW_VALUE, _share.to_string());
+    // applies to purchases made from now on
+    project.referral_share = _share;
+    PROJECTS.save(_deps.storage, &projects)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_UPDATE_REFERRAL_SHARE)
+        .add_event(event))
+}
+
+pub fn execute_claim_referral_rewards(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let rewards = REFERRAL_REWARDS
+        .may_load(_deps.storage, &_info.sender)?
+        .unwrap_or_default();
+    if rewards.is_empty() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    REFERRAL_REWARDS.remove(_deps.storage, &_info.sender);
+    let mut event = neariot_event(ACTION_CLAIM_REFERRAL_REWARDS, &_info.sender);
+    for reward in rewards.iter() {
+        event = event
+            .add_attribute(ATTR_AMOUNT, reward.amount)
+            .add_attribute(ATTR_DENOM, reward.denom.clone());
+    }
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: _info.sender.to_string(),
+            amount: rewards,
+        })
+        .add_attribute("action", ACTION_CLAIM_REFERRAL_REWARDS)
+        .add_event(event))
+}
+
+// `generate_id` repeats for one sender within a block, so anything a sender can
+// create several of at once is numbered from a counter instead
+fn next_id(storage: &mut dyn Storage) -> StdResult<String> {
+    let id = ID_COUNTER.may_load(storage)?.unwrap_or_default() + 1;
+    ID_COUNTER.save(storage, &id)?;
+    Ok(id.to_string())
+}
+
+fn update_reputation(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    update: impl FnOnce(&mut Reputation),
+) -> StdResult<()> {
+    let mut reputation = REPUTATIONS.may_load(storage, owner)?.unwrap_or_default();
+    update(&mut reputation);
+    REPUTATIONS.save(storage, owner, &reputation)
+}
+
+/// What the project still shows of its owner's counters. Purchases cancelled
+/// outright leave no trace, so their backings and refunds stay with whoever
+/// owned the project then. Completed and failed campaigns are counted by
+/// `GetReputation` for the current owner, so only a cancel is moved here.
+fn project_reputation(storage: &dyn Storage, project: &Project) -> StdResult<Reputation> {
+    let mut record = Reputation {
+        campaigns: (project.status != ProjectStatus::Draft) as u32,
+        failed_campaigns: (project.status == ProjectStatus::Cancelled) as u32,
+        backings: (project.bougth_offers.len() + project.pledges.len()) as u32,
+        ..Reputation::default()
+    };
+    for purchase in project.bougth_offers.iter() {
+        if !purchase.rate.is_zero() {
+            record.rating_sum += purchase.rate;
+            record.rating_count += 1;
+        }
+        if let Some(shipment) = purchase.shipment.as_ref() {
+            record.shipments += 1;
+            if shipment.confirmed_at.is_some() {
+                record.confirmed_deliveries += 1;
+            }
+        }
+    }
+    let dispute_ids = PROJECT_DISPUTES
+        .prefix(project.id.as_str())
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for dispute_id in dispute_ids {
+        let dispute = DISPUTES.load(storage, &dispute_id)?;
+        record.disputes += 1;
+        match dispute.status {
+            DisputeStatus::Refunded | DisputeStatus::PartiallyRefunded => {
+                record.disputes_upheld += 1;
+                record.refunds += 1;
+            }
+            DisputeStatus::Rejected => record.disputes_rejected += 1,
+            DisputeStatus::Open | DisputeStatus::Closed => {}
+        }
+    }
+    Ok(record)
+}
+
+pub fn credit_refund(storage: &mut dyn Storage, recipient: &Addr, refund: Coin) -> StdResult<()> {
+    let mut balance = NativeBalance(REFUNDS.may_load(storage, recipient)?.unwrap_or_default());
+    balance += refund;
+    REFUNDS.save(storage, recipient, &balance.into_vec())
+}
+
+pub fn execute_update_access_list(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _list: AccessList,
+    _add: Vec<String>,
+    _remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    assert_admin(_deps.storage, &_info.sender)?;
+    let mut access = ACCESS.may_load(_deps.storage)?.unwrap_or_default();
+    let addresses = match _list {
+        AccessList::BlockedCreators => &mut access.blocked_creators,
+        AccessList::BlockedBuyers => &mut access.blocked_buyers,
+        AccessList::AllowedCreators => &mut access.allowed_creators,
+    };
+    let event = neariot_event(ACTION_UPDATE_ACCESS_LIST, &_info.sender)
+        .add_attribute(ATTR_OLD_VALUE, _remove.join(","))
+        .add_attribute(ATTR_NEW_VALUE, _add.join(","));
+    for address in _add.iter() {
+        let address = _deps.api.addr_validate(address)?;
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+    addresses.retain(|address| !_remove.contains(&address.to_string()));
+    ACCESS.save(_deps.storage, &access)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_UPDATE_ACCESS_LIST)
+        .add_event(event))
+}
+
+pub fn execute_set_verified_creators_only(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _enabled: bool,
+) -> Result<Response, ContractError> {
+    assert_admin(_deps.storage, &_info.sender)?;
+    let mut access = ACCESS.may_load(_deps.storage)?.unwrap_or_default();
+    let event = neariot_event(ACTION_SET_VERIFIED_CREATORS_ONLY, &_info.sender)
+        .add_attribute(ATTR_OLD_VALUE, access.verified_creators_only.to_string())
+        .add_attribute(ATTR_NEW_VALUE, _enabled.to_string());
+    access.verified_creators_only = _enabled;
+    ACCESS.save(_deps.storage, &access)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_SET_VERIFIED_CREATORS_ONLY)
+        .add_event(event))
+}
+
+pub fn execute_add_verifier(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _address: String,
+) -> Result<Response, ContractError> {
+    assert_admin(_deps.storage, &_info.sender)?;
+    let verifier = _deps.api.addr_validate(&_address)?;
+    let mut verifiers = VERIFIERS.may_load(_deps.storage)?.unwrap_or_default();
+    if !verifiers.contains(&verifier) {
+        verifiers.push(verifier);
+    }
+    VERIFIERS.save(_deps.storage, &verifiers)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_ADD_VERIFIER)
+        .add_event(
+            neariot_event(ACTION_ADD_VERIFIER, &_info.sender)
+                .add_attribute(ATTR_NEW_VALUE, _address),
+        ))
+}
+
+pub fn execute_remove_verifier(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _address: String,
+) -> Result<Response, ContractError> {
+    assert_admin(_deps.storage, &_info.sender)?;
+    let mut verifiers = VERIFIERS.may_load(_deps.storage)?.unwrap_or_default();
+    verifiers.retain(|verifier| verifier.as_str() != _address);
+    VERIFIERS.save(_deps.storage, &verifiers)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_REMOVE_VERIFIER)
+        .add_event(
+            neariot_event(ACTION_REMOVE_VERIFIER, &_info.sender)
+                .add_attribute(ATTR_OLD_VALUE, _address),
+        ))
+}
+
+pub fn execute_grant_verification(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _address: String,
+    _expire_at: Option<u64>,
+) -> Result<Response, ContractError> {
+    assert_verifier(_deps.storage, &_info.sender)?;
+    let mut users = USERS.load(_deps.storage).unwrap_or_default();
+    let user = users
+        .iter_mut()
+        .find(|user| user.address.as_str() == _address)
+        .ok_or(ContractError::UserNotFound {})?;
+    user.verification = Some(Verification {
+        verifier: _info.sender.clone(),
+        granted_at: _env.block.time,
+        expire_at: _expire_at.map(Timestamp::from_seconds),
+    });
+    USERS.save(_deps.storage, &users)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_GRANT_VERIFICATION)
+        .add_event(
+            neariot_event(ACTION_GRANT_VERIFICATION, &_info.sender)
+                .add_attribute(ATTR_NEW_VALUE, _address),
+        ))
+}
+
+pub fn execute_revoke_verification(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _address: String,
+) -> Result<Response, ContractError> {
+    assert_verifier(_deps.storage, &_info.sender)?;
+    let mut users = USERS.load(_deps.storage).unwrap_or_default();
+    let user = users
+        .iter_mut()
+        .find(|user| user.address.as_str() == _address)
+        .ok_or(ContractError::UserNotFound {})?;
+    user.verification = None;
+    USERS.save(_deps.storage, &users)?;
+    Ok(Response::new()
+        .add_attribute("action", ACTION_REVOKE_VERIFICATION)
+        .add_event(
+            neariot_event(ACTION_REVOKE_VERIFICATION, &_info.sender)
+                .add_attribute(ATTR_OLD_VALUE, _address),
+        ))
+}
+
+/// Admin is always allowed to verify users, next to the appointed verifiers.
+pub fn assert_verifier(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    let verifiers = VERIFIERS.may_load(storage)?.unwrap_or_default();
+    if !verifiers.contains(sender) {
+        assert_admin(storage, sender)?;
+    }
+    Ok(())
+}
+
+pub fn assert_can_create(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    let access = ACCESS.may_load(storage)?.unwrap_or_default();
+    if access.blocked_creators.contains(sender) {
+        return Err(ContractError::CreatorBlocked {});
+    }
+    if access.verified_creators_only && !access.allowed_creators.contains(sender) {
+        return Err(ContractError::CreatorNotAllowed {});
+    }
+    Ok(())
+}
+
+pub fn assert_can_buy(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    let access = ACCESS.may_load(storage)?.unwrap_or_default();
+    if access.blocked_buyers.contains(sender) {
+        return Err(ContractError::BuyerBlocked {});
+    }
+    Ok(())
+}
+
+pub fn assert_admin(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    if ADMIN.load(storage)? != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Fails with `Paused` if the contract is paused globally or, when given,
+/// the operation family is paused.
+pub fn assert_not_paused(
+    storage: &dyn Storage,
+    operation: Option<PauseOperation>,
+) -> Result<(), ContractError> {
+    let pause = PAUSE.may_load(storage)?.unwrap_or_default();
+    if pause.global {
+        return Err(ContractError::Paused {});
+    }
+    if let Some(operation) = operation {
+        if pause.operations.contains(&operation) {
+            return Err(ContractError::Paused {});
+        }
+    }
+    Ok(())
+}
+
+pub fn query_get_user(_deps: Deps, _id: Addr) -> StdResult<User> {
+    let users = USERS.load(_deps.storage)?;
+    let user = users.iter().find(|user| user.name == _id).unwrap();
+    Ok(user.to_owned())
+}
+
+pub fn query_list_user(_deps: Deps) -> StdResult<Vec<User>> {
+    let users = USERS.load(_deps.storage)?;
+    Ok(users.to_owned())
+}
+
+pub fn query_get_project(_deps: Deps, _env: Env, _id: String) -> StdResult<ProjectResponse> {
+    let projects = PROJECTS.load(_deps.storage)?;
+    let project = projects
+        .iter()
+        .find(|project| project.id == _id)
+        .ok_or_else(|| StdError::not_found("Project"))?;
+    let users = USERS.may_load(_deps.storage)?.unwrap_or_default();
+    Ok(to_project_response(&users, &_env, project.to_owned()))
+}
+
+pub fn query_list_project(
+    _deps: Deps,
+    _env: Env,
+    _status: Option<ProjectStatus>,
+) -> StdResult<Vec<ProjectResponse>> {
+    let projects = PROJECTS.load(_deps.storage)?;
+    let users = USERS.may_load(_deps.storage)?.unwrap_or_default();
+    Ok(projects
+        .into_iter()
+        .filter(|project| !project.hidden)
+        .map(|project| to_project_response(&users, &_env, project))
+        .filter(|response| {
+            _status
+                .as_ref()
+                .map_or(true, |status| &response.project.status == status)
+        })
+        .collect())
+}
+
+fn to_project_response(users: &[User], env: &Env, mut project: Project) -> ProjectResponse {
+    project.status = project_status(env, &project);
+    let owner_verified = users
+        .iter()
+        .any(|user| user.address == project.owner && user.is_verified(env.block.time));
+    ProjectResponse {
+        fulfillment: fulfillment_stats(env, &project),
+        project,
+        owner_verified,
+    }
+}
+
+fn fulfillment_stats(env: &Env, project: &Project) -> FulfillmentStats {
+    let purchases = project.bougth_offers.len() as u32;
+    let shipments = project
+        .bougth_offers
+        .iter()
+        .filter_map(|offer| offer.shipment.as_ref());
+    let shipped = shipments.clone().count() as u32;
+    let delivered = shipments
+        .filter(|shipment| shipment.is_delivered(env.block.time, AUTO_CONFIRM_PERIOD))
+        .count() as u32;
+    FulfillmentStats {
+        purchases,
+        shipped,
+        delivered,
+        fulfillment_rate: if purchases == 0 {
+            Decimal::zero()
+        } else {
+            Decimal::from_ratio(delivered, purchases)
+        },
+    }
+}
+
+pub fn query_list_promo_codes(_deps: Deps, _id: String) -> StdResult<Vec<PromoCode>> {
+    PROMO_CODES
+        .prefix(&_id)
+        .range(_deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, promo)| promo))
+        .collect()
+}
+
+pub fn query_get_milestones(_deps: Deps, _id: String) -> StdResult<Vec<Milestone>> {
+    let projects = PROJECTS.load(_deps.storage)?;
+    let project = projects
+        .iter()
+        .find(|project| project.id == _id)
+        .ok_or_else(|| StdError::not_found("Project"))?;
+    Ok(project.milestones.to_owned())
+}
+
+pub fn query_get_stretch_goals(_deps: Deps, _id: String) -> StdResult<Vec<StretchGoal>> {
+    let projects = PROJECTS.load(_deps.storage)?;
+    let project = projects
+        .iter()
+        .find(|project| project.id == _id)
+        .ok_or_else(|| StdError::not_found("Project"))?;
+    Ok(project.stretch_goals.to_owned())
+}
+
+pub fn query_get_reputation(
+    _deps: Deps,
+    _env: Env,
+    _owner: String,
+) -> StdResult<ReputationResponse> {
+    let owner = _deps.api.addr_validate(&_owner)?;
+    let mut reputation = REPUTATIONS
+        .may_load(_deps.storage, &owner)?
+        .unwrap_or_default();
+    // a cancel moves a failed campaign to Cancelled and a completed one can't
+    // be cancelled, so none is counted twice
+    let projects = PROJECTS.may_load(_deps.storage)?.unwrap_or_default();
+    for project in projects.iter().filter(|project| project.owner == owner) {
+        match project_status(&_env, project) {
+            ProjectStatus::Completed => reputation.completed_campaigns += 1,
+            ProjectStatus::Failed => reputation.failed_campaigns += 1,
+            _ => {}
+        }
+    }
+    let avg_rating = if reputation.rating_count == 0 {
+        Uint128::zero()
+    } else {
+        reputation.rating_sum / Uint128::from(reputation.rating_count)
+    };
+    // each ratio is good / total, skipped when total is zero
+    let ratios = [
+        (
+            reputation.completed_campaigns,
+            reputation.completed_campaigns + reputation.failed_campaigns,
+        ),
+        (
+            reputation.backings.saturating_sub(reputation.refunds),
+            reputation.backings,
+        ),
+        (
+            reputation
+                .disputes
+                .saturating_sub(reputation.disputes_upheld),
+            reputation.disputes,
+        ),
+        (reputation.confirmed_deliveries, reputation.shipments),
+    ];
+    let known = ratios
+        .iter()
+        .filter(|(_, total)| *total > 0)
+        .map(|(good, total)| Decimal::from_ratio(*good, *total))
+        .collect::<Vec<_>>();
+    let score = (!known.is_empty()).then(|| {
+        known.iter().fold(Decimal::zero(), |sum, ratio| sum + ratio)
+            / Decimal::from_ratio(known.len() as u128, 1u128)
+    });
+    Ok(ReputationResponse {
+        owner,
+        reputation,
+        avg_rating,
+        score,
+    })
+}
+
+pub fn query_get_funding(_deps: Deps, _env: Env, _id: String) -> StdResult<FundingResponse> {
+    let projects = PROJECTS.load(_deps.storage)?;
+    let project = projects
+        .iter()
+        .find(|project| project.id == _id)
+        .ok_or_else(|| StdError::not_found("Project"))?;
+    Ok(to_funding_response(&_env, project))
+}
+
+pub fn query_list_funding(
+    _deps: Deps,
+    _env: Env,
+    _status: Option<FundingStatus>,
+    _start_after: Option<String>,
+    _limit: Option<u32>,
+) -> StdResult<Vec<FundingResponse>> {
+    let limit = _limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let projects = PROJECTS.may_load(_deps.storage)?.unwrap_or_default();
+    // projects are kept in creation order, so page by position after `start_after`
+    let start = match _start_after {
+        Some(start_after) => projects
+            .iter()
+            .position(|project| project.id == start_after)
+            .map_or(projects.len(), |position| position + 1),
+        None => 0,
+    };
+    Ok(projects[start..]
+        .iter()
+        .filter(|project| !project.hidden)
+        .map(|project| to_funding_response(&_env, project))
+        .filter(|funding| {
+            _status
+                .as_ref()
+                .map_or(true, |status| &funding.status == status)
+        })
+        .take(limit)
+        .collect())
+}
+
+/// `total_pledged` is compared with the goal, so a project is funded in one
+/// denom only.
+fn assert_funding_denom(project: &mut Project, denom: &str) -> Result<(), ContractError> {
+    match project.goal_denom.as_deref() {
+        Some(goal_denom) if goal_denom != denom => Err(ContractError::InvalidFundingDenom {
+            denom: goal_denom.to_string(),
+        }),
+        Some(_) => Ok(()),
+        None => {
+            project.goal_denom = Some(denom.to_string());
+            Ok(())
+        }
+    }
+}
+
+fn to_funding_response(env: &Env, project: &Project) -> FundingResponse {
+    let mut backers: Vec<&Addr> = vec![];
+    if project.status != ProjectStatus::Cancelled {
+        let buyers = project.bougth_offers.iter().map(|offer| &offer.buyer);
+        let pledgers = project.pledges.iter().map(|pledge| &pledge.backer);
+        for backer in buyers.chain(pledgers) {
+            if !backers.contains(&backer) {
+                backers.push(backer);
+            }
+        }
+    }
+    let percent_funded = if project.goal.is_zero() {
+        0
+    } else {
+        u64::try_from(
+            project
+                .total_pledged
+                .multiply_ratio(100u128, project.goal)
+                .u128(),
+        )
+        .unwrap_or(u64::MAX)
+    };
+    FundingResponse {
+        project_id: project.id.clone(),
+        goal: project.goal,
+        goal_denom: project.goal_denom.clone(),
+        total_pledged: project.total_pledged,
+        raised: project_raised(project).into_vec(),
+        backers: backers.len() as u64,
+        percent_funded,
+        deadline: project.milestone,
+        status: funding_status(env, project),
+    }
+}
+
+/// Everything backers paid in, per denom. Nothing is raised once the project
+/// is cancelled since all purchases were refunded.
+fn project_raised(project: &Project) -> NativeBalance {
+    let mut raised = NativeBalance::default();
+    if project.status != ProjectStatus::Cancelled {
+        for offer in project.bougth_offers.iter() {
+            raised += coin(offer.price.u128(), &offer.denom);
+        }
+        for pledge in project.pledges.iter() {
+            raised += coin(pledge.amount.u128(), &pledge.denom);
+        }
+        for fee in project.cancel_fees.iter() {
+            raised += fee.clone();
+        }
+    }
+    raised.normalize();
+    raised
+}
+
+/// Funds released to the owner so far: everything not assigned to a milestone
+/// plus the tranches of completed milestones, less what was already withdrawn.
+fn project_withdrawable(project: &Project) -> NativeBalance {
+    let locked = project
+        .milestones
+        .iter()
+        .filter(|milestone| milestone.completed_at.is_none())
+        .fold(Decimal::zero(), |locked, milestone| {
+            locked + milestone.tranche
+        });
+    let unlocked_share = Decimal::one() - locked.min(Decimal::one());
+    let unlocked = NativeBalance(
+        project_raised(project)
+            .into_vec()
+            .into_iter()
+            .map(|raised| coin((raised.amount * unlocked_share).u128(), raised.denom))
+            .collect(),
+    );
+    saturating_sub(
+        &saturating_sub(&unlocked, &NativeBalance(project.withdrawn.clone())),
+        &NativeBalance(project.frozen.clone()),
+    )
+}
+
+/// Funds still held by the contract on behalf of the project.
+fn project_escrow(project: &Project) -> NativeBalance {
+    saturating_sub(
+        &project_raised(project),
+        &NativeBalance(project.withdrawn.clone()),
+    )
+}
+
+fn balance_of(balance: &NativeBalance, denom: &str) -> Uint128 {
+    balance
+        .0
+        .iter()
+        .find(|coin| coin.denom == denom)
+        .map_or(Uint128::zero(), |coin| coin.amount)
+}
+
+/// Per denom `minuend - subtrahend`, dropping denoms that reach zero.
+fn saturating_sub(minuend: &NativeBalance, subtrahend: &NativeBalance) -> NativeBalance {
+    NativeBalance(
+        minuend
+            .0
+            .iter()
+            .filter_map(|held| {
+                let taken = subtrahend
+                    .0
+                    .iter()
+                    .find(|taken| taken.denom == held.denom)
+                    .map_or(Uint128::zero(), |taken| taken.amount);
+                let left = held.amount.saturating_sub(taken);
+                (!left.is_zero()).then(|| coin(left.u128(), &held.denom))
+            })
+            .collect(),
+    )
+}
+
+pub fn query_get_balance(_deps: Deps, _env: Env) -> StdResult<BalanceResponse> {
+    let mut escrow = NativeBalance::default();
+    let projects = PROJECTS.may_load(_deps.storage)?.unwrap_or_default();
+    for project in projects.iter() {
+        for held in project_escrow(project).into_vec() {
+            escrow += held;
+        }
+    }
+    // prepaid subscription periods the owner hasn't claimed yet
+    for subscription in SUBSCRIPTIONS.range(_deps.storage, None, None, Order::Ascending) {
+        let subscription = subscription?.1;
+        let unclaimed = subscription.periods - subscription.claimed;
+        if unclaimed > 0 {
+            escrow += coin(
+                (subscription.amount_per_period * Uint128::from(unclaimed)).u128(),
+                &subscription.denom,
+            );
+        }
+    }
+    for refunds in REFUNDS.range(_deps.storage, None, None, Order::Ascending) {
+        for refund in refunds?.1 {
+            escrow += refund;
+        }
+    }
+    for rewards in REFERRAL_REWARDS.range(_deps.storage, None, None, Order::Ascending) {
+        for reward in rewards?.1 {
+            escrow += reward;
+        }
+    }
+    escrow.normalize();
+
+    let actual = NativeBalance(_deps.querier.query_all_balances(&_env.contract.address)?);
+    Ok(BalanceResponse {
+        surplus: saturating_sub(&actual, &escrow).into_vec(),
+        shortfall: saturating_sub(&escrow, &actual).into_vec(),
+        escrow: escrow.into_vec(),
+        actual: actual.into_vec(),
+    })
+}
+
+pub fn query_get_project_balance(
+    _deps: Deps,
+    _env: Env,
+    _id: String,
+) -> StdResult<ProjectBalanceResponse> {
+    let projects = PROJECTS.load(_deps.storage)?;
+    let project = projects
+        .iter()
+        .find(|project| project.id == _id)
+        .ok_or_else(|| StdError::not_found("Project"))?;
+    let escrowed = project_escrow(project).into_vec();
+    let withdrawable = if funding_status(&_env, project) == FundingStatus::Succeeded {
+        let available = project_withdrawable(project);
+        let mut rewards = NativeBalance::default();
+        for (_, _, reward) in payable_referral_rewards(project, &available) {
+            rewards += reward;
+        }
+        saturating_sub(&available, &rewards).into_vec()
+    } else {
+        vec![]
+    };
+    Ok(ProjectBalanceResponse {
+        project_id: project.id.clone(),
+        escrowed,
+        withdrawable,
+        withdrawn: project.withdrawn.clone(),
+    })
+}
+
+pub fn query_get_user_balance(_deps: Deps, _address: String) -> StdResult<UserBalanceResponse> {
+    let address = _deps.api.addr_validate(&_address)?;
+    let claimable_refunds = REFUNDS
+        .may_load(_deps.storage, &address)?
+        .unwrap_or_default();
+    let claimable_rewards = REFERRAL_REWARDS
+        .may_load(_deps.storage, &address)?
+        .unwrap_or_default();
+    Ok(UserBalanceResponse {
+        address,
+        claimable_refunds,
+        claimable_rewards,
+    })
+}
+
+pub fn query_get_referral_stats(_deps: Deps, _address: String) -> StdResult<ReferralStatsResponse> {
+    let address = _deps.api.addr_validate(&_address)?;
+    let stats = REFERRAL_STATS
+        .may_load(_deps.storage, &address)?
+        .unwrap_or_default();
+    let mut pending = NativeBalance::default();
+    for project in PROJECTS.may_load(_deps.storage)?.unwrap_or_default().iter() {
+        for (referrer, reward) in unsettled_referral_rewards(project) {
+            if referrer == address {
+                pending += reward;
+            }
+        }
+    }
+    let claimable = REFERRAL_REWARDS
+        .may_load(_deps.storage, &address)?
+        .unwrap_or_default();
+    Ok(ReferralStatsResponse {
+        address,
+        referrals: stats.referrals,
+        volume: stats.volume,
+        pending: pending.into_vec(),
+        earned: stats.earned,
+        claimable,
+    })
+}
+
+/// A project without a deadline (`milestone` never moved past creation) is
+/// always active; otherwise it settles against its goal once the deadline passes.
+fn funding_status(env: &Env, project: &Project) -> FundingStatus {
+    match project_status(env, project) {
+        ProjectStatus::Draft | ProjectStatus::Live => FundingStatus::Active,
+        ProjectStatus::Funded | ProjectStatus::Delivering | ProjectStatus::Completed => {
+            FundingStatus::Succeeded
+        }
+        ProjectStatus::Failed => FundingStatus::Failed,
+        ProjectStatus::Cancelled => FundingStatus::Cancelled,
+    }
+}
+
+/// The stored status with the transitions that happen on their own applied:
+/// the deadline closing a live campaign, and the last delivery or milestone
+/// completing a delivering one. A failed milestone keeps the project
+/// delivering, its tranche can only go back to backers through a cancel.
+fn project_status(env: &Env, project: &Project) -> ProjectStatus {
+    match project.status {
+        ProjectStatus::Live
+            if project.milestone > project.create_at && env.block.time >= project.milestone =>
+        {
+            if project.total_pledged >= project.goal {
+                ProjectStatus::Funded
+            } else {
+                ProjectStatus::Failed
+            }
+        }
+        ProjectStatus::Delivering
+            if project.frozen.is_empty()
+                && project
+                    .milestones
+                    .iter()
+                    .all(|milestone| milestone.completed_at.is_some())
+                && project.bougth_offers.iter().all(|offer| {
+                    offer.shipment.as_ref().map_or(false, |shipment| {
+                        shipment.is_delivered(env.block.time, AUTO_CONFIRM_PERIOD)
+                    })
+                }) =>
+        {
+            ProjectStatus::Completed
+        }
+        _ => project.status.clone(),
+    }
+}
+
+/// Brings `project.status` up to date and fails unless it is one of `allowed`.
+fn assert_status(
+    env: &Env,
+    project: &mut Project,
+    allowed: &[ProjectStatus],
+) -> Result<(), ContractError> {
+    project.status = project_status(env, project);
+    if allowed.contains(&project.status) {
+        return Ok(());
+    }
+    match project.status {
+        ProjectStatus::Cancelled => Err(ContractError::ProjectCancelled {}),
+        _ => Err(ContractError::InvalidProjectStatus {
+            status: project.status.clone(),
+        }),
+    }
+}
+
+fn transition(project: &mut Project, next: ProjectStatus) -> Result<(), ContractError> {
+    if project.status == ProjectStatus::Cancelled {
+        return Err(ContractError::ProjectCancelled {});
+    }
+    if !project.status.can_transition_to(&next) {
+        return Err(ContractError::InvalidStatusTransition {
+            from: project.status.clone(),
+            to: next,
+        });
+    }
+    project.status = next;
+    Ok(())
+}
+
+/// Backers of a project, largest contribution in the goal's denom first.
+pub fn query_list_project_contributions(
+    _deps: Deps,
+    _project_id: String,
+    _start_after: Option<String>,
+    _limit: Option<u32>,
+) -> StdResult<Vec<Contribution>> {
+    let limit = _limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut contributions = CONTRIBUTIONS
+        .prefix(_project_id.as_str())
+        .range(_deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, contribution)| contribution))
+        .collect::<StdResult<Vec<_>>>()?;
+    let denom = PROJECTS
+        .may_load(_deps.storage)?
+        .unwrap_or_default()
+        .into_iter()
+        .find(|project| project.id == _project_id)
+        .and_then(|project| project.goal_denom)
+        .unwrap_or_default();
+    contributions.sort_by(|a, b| {
+        b.amount_of(&denom)
+            .cmp(&a.amount_of(&denom))
+            .then(a.backer.cmp(&b.backer))
+    });
+    let start = match _start_after {
+        Some(start_after) => contributions
+            .iter()
+            .position(|contribution| contribution.backer.as_str() == start_after)
+            .map_or(contributions.len(), |position| position + 1),
+        None => 0,
+    };
+    Ok(contributions.into_iter().skip(start).take(limit).collect())
+}
+
+pub fn query_list_backer_contributions(
+    _deps: Deps,
+    _backer: String,
+    _start_after: Option<String>,
+    _limit: Option<u32>,
+) -> StdResult<Vec<Contribution>> {
+    let limit = _limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let backer = _deps.api.addr_validate(&_backer)?;
+    let start = _start_after.as_deref().map(Bound::exclusive);
+    BACKED_PROJECTS
+        .prefix(&backer)
+        .keys(_deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|project_id| CONTRIBUTIONS.load(_deps.storage, (project_id?.as_str(), &backer)))
+        .collect()
+}
+
+pub fn query_list_subscriptions(
+    _deps: Deps,
+    _project_id: String,
+    _start_after: Option<String>,
+    _limit: Option<u32>,
+) -> StdResult<Vec<Subscription>> {
+    let limit = _limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = _start_after.as_deref().map(Bound::exclusive);
+    SUBSCRIPTIONS
+        .prefix(_project_id.as_str())
+        .range(_deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, subscription)| subscription))
+        .collect()
+}
+
+pub fn query_get_device(_deps: Deps, _project_id: String, _device_id: String) -> StdResult<Device> {
+    DEVICES.load(_deps.storage, (&_project_id, &_device_id))
+}
+
+pub fn query_list_devices(
+    _deps: Deps,
+    _project_id: String,
+    _start_after: Option<String>,
+    _limit: Option<u32>,
+) -> StdResult<Vec<Device>> {
+    let limit = _limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = _start_after.as_deref().map(Bound::exclusive);
+    DEVICES
+        .prefix(_project_id.as_str())
+        .range(_deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, device)| device))
+        .collect()
+}
+
+pub fn query_get_attestation(
+    _deps: Deps,
+    _project_id: String,
+    _device_id: String,
+) -> StdResult<Option<Attestation>> {
+    ATTESTATIONS.may_load(_deps.storage, (&_project_id, &_device_id))
+}
+
+pub fn query_get_project_offers(
+    _deps: Deps,
+    _env: Env,
+    _id: String,
+) -> StdResult<Vec<OfferResponse>> {
+    let projects = PROJECTS.load(_deps.storage)?;
+    let project = projects
+        .iter()
+        .find(|project| project.id == _id)
+        .ok_or_else(|| StdError::not_found("Project"))?;
+    Ok(project
+        .offers
+        .iter()
+        .map(|offer| to_offer_response(&_env, project, offer))
+        .collect())
+}
+
+pub fn query_get_project_offer(
+    _deps: Deps,
+    _env: Env,
+    _id: String,
+    _offer_id: String,
+) -> StdResult<OfferResponse> {
+    let projects = PROJECTS.load(_deps.storage)?;
+    let project = projects
+        .iter()
+        .find(|project| project.id == _id)
+        .ok_or_else(|| StdError::not_found("Project"))?;
+    let offer = project
+        .offers
+        .iter()
+        .find(|offer| offer.id == _offer_id)
+        .ok_or_else(|| StdError::not_found("Offer"))?;
+    Ok(to_offer_response(&_env, project, offer))
+}
+
+fn to_offer_response(env: &Env, project: &Project, offer: &Offer) -> OfferResponse {
+    let sold = offers_sold(project, &offer.id);
+    OfferResponse {
+        offer: offer.to_owned(),
+        sold,
+        current_price: offer.current_price(env.block.time, sold),
+    }
+}
+
+pub fn query_get_admin(_deps: Deps) -> StdResult<Addr> {
+    let admin = ADMIN.load(_deps.storage)?;
+    Ok(admin.to_owned())
+}
+
+pub fn query_list_moderation(
+    _deps: Deps,
+    _project_id: Option<String>,
+) -> StdResult<Vec<ModerationRecord>> {
+    let log = MODERATION_LOG.may_load(_deps.storage)?.unwrap_or_default();
+    Ok(log
+        .into_iter()
+        .filter(|record| {
+            _project_id
+                .as_ref()
+                .map_or(true, |project_id| &record.project_id == project_id)
+        })
+        .collect())
+}
+
+pub fn query_get_access(_deps: Deps) -> StdResult<AccessConfig> {
+    let access = ACCESS.may_load(_deps.storage)?.unwrap_or_default();
+    Ok(access)
+}
+
+pub fn query_list_verifiers(_deps: Deps) -> StdResult<Vec<Addr>> {
+    let verifiers = VERIFIERS.may_load(_deps.storage)?.unwrap_or_default();
+    Ok(verifiers)
+}
+
+pub fn query_list_oracles(_deps: Deps, _project_id: Option<String>) -> StdResult<Vec<Addr>> {
+    let oracles = match _project_id {
+        Some(project_id) => PROJECT_ORACLES.may_load(_deps.storage, &project_id)?,
+        None => GLOBAL_ORACLES.may_load(_deps.storage)?,
+    };
+    Ok(oracles.unwrap_or_default())
+}
+
+pub fn query_list_arbitrators(_deps: Deps) -> StdResult<Vec<Addr>> {
+    let arbitrators = ARBITRATORS.may_load(_deps.storage)?.unwrap_or_default();
+    Ok(arbitrators)
+}
+
+pub fn query_get_dispute(_deps: Deps, _dispute_id: String) -> StdResult<Dispute> {
+    DISPUTES.load(_deps.storage, &_dispute_id)
+}
+
+pub fn query_list_disputes(
+    _deps: Deps,
+    _project_id: Option<String>,
+    _status: Option<DisputeStatus>,
+    _start_after: Option<String>,
+    _limit: Option<u32>,
+) -> StdResult<Vec<Dispute>> {
+    let limit = _limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = _start_after.as_deref().map(Bound::exclusive);
+    DISPUTES
+        .range(_deps.storage, start, None, Order::Ascending)
+        .map(|item| item.map(|(_, dispute)| dispute))
+        .filter(|dispute| {
+            dispute.as_ref().map_or(true, |dispute| {
+                _project_id
+                    .as_ref()
+                    .map_or(true, |project_id| &dispute.project_id == project_id)
+                    && _status
+                        .as_ref()
+                        .map_or(true, |status| &dispute.status == status)
+            })
+        })
+        .take(limit)
+        .collect()
+}
+
+pub fn query_get_pause(_deps: Deps) -> StdResult<PauseConfig> {
+    let pause = PAUSE.may_load(_deps.storage)?.unwrap_or_default();
+    Ok(pause)
+}
+
+// #[inline]
+// fn coin_to_string(amount: Uint128, denom: &str) -> String {
+//     format!("{} {}", amount, denom)
+// }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{coins, from_binary, BankMsg, Coin, CosmosMsg, OwnedDeps};
+    use ed25519_zebra::{SigningKey, VerificationKey};
+
+    fn setup() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {};
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        deps
+    }
+
+    fn mock_project(mut deps: DepsMut, owner: &str) -> String {
+        let project_id = mock_draft(deps.branch(), owner);
+        mock_launch(deps, owner, &project_id);
+        project_id
+    }
+
+    // goal and deadline can only be set before launch
+    fn mock_draft(deps: DepsMut, owner: &str) -> String {
+        let msg = ExecuteMsg::CreateProject {
+            metadata: "example".to_string(),
+        };
+        let res = execute(deps, mock_env(), mock_info(owner, &[]), msg).unwrap();
+        res.attributes[1].clone().value
+    }
+
+    fn mock_launch(deps: DepsMut, owner: &str, project_id: &str) {
+        let msg = ExecuteMsg::LaunchProject {
+            id: project_id.to_string(),
+        };
+        let _res = execute(deps, mock_env(), mock_info(owner, &[]), msg).unwrap();
+    }
+
+    fn mock_offer(deps: DepsMut, owner: &str, project_id: &str, min_price: u128) -> String {
+        let msg = ExecuteMsg::CreateOffer {
+            id: project_id.to_string(),
+            min_price: Uint128::from(min_price),
+            metadata: "example".to_string(),
+            expire_at: 0,
+        };
+        let info = mock_info(owner, &[]);
+        let res = execute(deps, mock_env(), info, msg).unwrap();
+        res.events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_OFFER_ID)
+            .unwrap()
+            .value
+            .clone()
+    }
+
+    fn mock_buy(
+        deps: DepsMut,
+        buyer: &str,
+        project_id: &str,
+        offer_id: &str,
+        funds: &[Coin],
+    ) -> String {
+        let msg = ExecuteMsg::BuyOffer {
+            project_id: project_id.to_string(),
+            offer_id: offer_id.to_string(),
+            metadata: "example".to_string(),
+            rate: Uint128::zero(),
+            promo_code: None,
+            referrer: None,
+        };
+        let info = mock_info(buyer, funds);
+        let res = execute(deps, mock_env(), info, msg).unwrap();
+        res.events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_PURCHASE_ID)
+            .unwrap()
+            .value
+            .clone()
+    }
+
+    fn mock_device(deps: DepsMut, owner: &str, project_id: &str, device_id: &str) -> SigningKey {
+        let seed: [u8; 32] = hash_code(device_id).as_slice().try_into().unwrap();
+        let signing_key = SigningKey::from(seed);
+        let pubkey: [u8; 32] = VerificationKey::from(&signing_key).into();
+        let msg = ExecuteMsg::RegisterDevice {
+            project_id: project_id.to_string(),
+            device_id: device_id.to_string(),
+            pubkey: Binary::from(pubkey),
+            key_type: KeyType::Ed25519,
+            model: "sensor-v1".to_string(),
+        };
+        let _res = execute(deps, mock_env(), mock_info(owner, &[]), msg).unwrap();
+        signing_key
+    }
+
+    fn mock_telemetry(
+        deps: DepsMut,
+        env: Env,
+        project_id: &str,
+        device_id: &str,
+        signing_key: &SigningKey,
+        counter: u64,
+    ) -> Response {
+        let payload_hash = hash_code("temperature=21.5");
+        let digest = telemetry_digest(project_id, device_id, &payload_hash, counter);
+        let signature: [u8; 64] = signing_key.sign(&digest).into();
+        let msg = ExecuteMsg::SubmitTelemetry {
+            project_id: project_id.to_string(),
+            device_id: device_id.to_string(),
+            payload_hash,
+            counter,
+            signature: Binary::from(signature),
+        };
+        execute(deps, env, mock_info("relayer", &[]), msg).unwrap()
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {};
+        let info = mock_info("creator", &coins(1000, "orai"));
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+    }
+
+    #[test]
+    fn register_user() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {};
+        let info = mock_info("creator", &coins(1000, "orai"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::RegisterUser {};
+        let info = mock_info("ciuz", &coins(1000, "orai"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        let msg = QueryMsg::GetUser {
+            id: Addr::unchecked("ciuz"),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let user: User = from_binary(&res).unwrap();
+        assert_eq!(user.total_spent, Uint128::zero());
+        assert_eq!(user.project_funded.len(), 0);
+        assert_eq!(user.project_watched.len(), 0);
+        assert_eq!(user.project_owned.len(), 0);
+    }
+
+    #[test]
+    fn create_project() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {};
+        let info = mock_info("creator", &coins(1000, "orai"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::RegisterUser {};
+        let info = mock_info("ciuz", &coins(1000, "orai"));
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::CreateProject {
+            metadata: "example".to_string(),
+        };
+        let info = mock_info("ciuz", &coins(1000, "orai"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        let project_id = res.attributes[1].clone().value;
+
+        let msg = QueryMsg::GetProject { id: project_id };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let project: ProjectResponse = from_binary(&res).unwrap();
+        assert_eq!(project.project.metadata, "example".to_string());
+
+        let msg = ExecuteMsg::UpdateProject {
+            id: "missing".to_string(),
+            metadata: "example".to_string(),
+        };
+        let info = mock_info("ciuz", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::ProjectNotFound {}));
+    }
+
+    #[test]
+    fn pause_contract() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {};
+        let info = mock_info("creator", &coins(1000, "orai"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only admin can pause
+        let msg = ExecuteMsg::Pause { operation: None };
+        let info = mock_info("ciuz", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let msg = ExecuteMsg::Pause {
+            operation: Some(PauseOperation::ProjectCreation),
+        };
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let scope = res.events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_NEW_VALUE)
+            .map(|attr| attr.value.clone());
+        assert_eq!(scope, Some("project_creation".to_string()));
+
+        let msg = ExecuteMsg::CreateProject {
+            metadata: "example".to_string(),
+        };
+        let info = mock_info("ciuz", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+
+        // other operations are not affected
+        let msg = ExecuteMsg::RegisterUser {};
+        let info = mock_info("ciuz", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Pause { operation: None };
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::RegisterUser {};
+        let info = mock_info("ciuz", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+
+        // queries keep working
+        let msg = QueryMsg::GetPause {};
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let pause: PauseConfig = from_binary(&res).unwrap();
+        assert!(pause.global);
+        assert_eq!(pause.operations, vec![PauseOperation::ProjectCreation]);
+
+        let msg = ExecuteMsg::Unpause { operation: None };
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::Unpause {
+            operation: Some(PauseOperation::ProjectCreation),
+        };
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::CreateProject {
+            metadata: "example".to_string(),
+        };
+        let info = mock_info("ciuz", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    fn moderate_project() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {};
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::CreateProject {
+            metadata: "example".to_string(),
+        };
+        let info = mock_info("ciuz", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let project_id = res.attributes[1].clone().value;
+        let msg = ExecuteMsg::LaunchProject {
+            id: project_id.clone(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::CreateOffer {
+            id: project_id.clone(),
+            min_price: Uint128::from(100u128),
+            metadata: "example".to_string(),
+            expire_at: 0,
+        };
+        let info = mock_info("ciuz", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = QueryMsg::GetProjectOffers {
+            project_id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let offers: Vec<OfferResponse> = from_binary(&res).unwrap();
+        let offer_id = offers[0].offer.id.clone();
+
+        let buy = ExecuteMsg::BuyOffer {
+            project_id: project_id.clone(),
+            offer_id,
+            metadata: "example".to_string(),
+            rate: Uint128::zero(),
+            promo_code: None,
+            referrer: None,
+        };
+        let info = mock_info("backer", &coins(150, "orai"));
+        let _res = execute(deps.as_mut(), mock_env(), info, buy.clone()).unwrap();
+
+        // only admin can moderate
+        let msg = ExecuteMsg::ModerateProject {
+            id: project_id.clone(),
+            action: ModerationAction::Flag,
+            reason: "copycat".to_string(),
+        };
+        let info = mock_info("ciuz", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("backer", &coins(150, "orai"));
+        let err = execute(deps.as_mut(), mock_env(), info, buy).unwrap_err();
+        assert!(matches!(err, ContractError::ProjectUnderReview {}));
+
+        let msg = ExecuteMsg::ModerateProject {
+            id: project_id.clone(),
+            action: ModerationAction::Hide,
+            reason: "copycat".to_string(),
+        };
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let action = res.events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_NEW_VALUE)
+            .map(|attr| attr.value.clone());
+        assert_eq!(action, Some("hide".to_string()));
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ListProject { status: None },
+        )
+        .unwrap();
+        let projects: Vec<ProjectResponse> = from_binary(&res).unwrap();
+        assert!(projects.is_empty());
+
+        let msg = ExecuteMsg::ModerateProject {
+            id: project_id.clone(),
+            action: ModerationAction::Cancel,
+            reason: "fraud".to_string(),
+        };
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let credit = res
+            .events
+            .iter()
+            .find(|event| event.attributes[0].value == ACTION_CREDIT_REFUND)
+            .unwrap();
+        assert_eq!(credit.attributes[1].value, "backer");
+        assert_eq!(credit.attributes[3].value, "150");
+        assert_eq!(credit.attributes[4].value, "orai");
+
+        let msg = ExecuteMsg::ClaimRefund {};
+        let info = mock_info("backer", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "backer".to_string(),
+                amount: coins(150, "orai"),
+            })
+        );
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::NoFunds {}));
+
+        let msg = QueryMsg::ListModeration {
+            project_id: Some(project_id),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let log: Vec<ModerationRecord> = from_binary(&res).unwrap();
+        assert_eq!(log.len(), 3);
+        assert_eq!(log[2].action, ModerationAction::Cancel);
+        assert_eq!(log[2].reason, "fraud".to_string());
+    }
+
+    #[test]
+    fn cancel_after_withdrawal() {
+        let mut deps = setup();
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 50);
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        // half of the proceeds stay locked behind a milestone
+        let msg = ExecuteMsg::AddMilestone {
+            id: project_id.clone(),
+            metadata: "prototype".to_string(),
+            tranche: Decimal::percent(50),
+            condition: MilestoneCondition::Oracle { quorum: 1 },
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+        mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(100, "orai"),
+        );
+        mock_buy(
+            deps.as_mut(),
+            "bob",
+            &project_id,
+            &offer_id,
+            &coins(50, "orai"),
+        );
+
+        let mut env = mock_env();
+        env.block.time = deadline;
+        let msg = ExecuteMsg::Withdraw {
+            project_id: project_id.clone(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("ciuz", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "ciuz".to_string(),
+                amount: coins(75, "orai"),
+            })
+        );
+
+        let msg = ExecuteMsg::ModerateProject {
+            id: project_id,
+            action: ModerationAction::Cancel,
+            reason: "fraud".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        // withdrawing didn't complete the campaign, so it only counts as failed
+        let msg = QueryMsg::GetReputation {
+            owner: "ciuz".to_string(),
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let reputation: ReputationResponse = from_binary(&res).unwrap();
+        assert_eq!(reputation.reputation.campaigns, 1);
+        assert_eq!(reputation.reputation.completed_campaigns, 0);
+        assert_eq!(reputation.reputation.failed_campaigns, 1);
+        for (backer, refund) in [("alice", 50), ("bob", 25)] {
+            let msg = QueryMsg::GetUserBalance {
+                address: backer.to_string(),
+            };
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            let balance: UserBalanceResponse = from_binary(&res).unwrap();
+            assert_eq!(balance.claimable_refunds, coins(refund, "orai"));
+        }
+    }
+
+    #[test]
+    fn failed_milestone_refund() {
+        let mut deps = setup();
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 50);
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        let msg = ExecuteMsg::AddOracle {
+            address: "courier".to_string(),
+            project_id: Some(project_id.clone()),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::AddMilestone {
+            id: project_id.clone(),
+            metadata: "prototype".to_string(),
+            tranche: Decimal::percent(50),
+            condition: MilestoneCondition::Oracle { quorum: 1 },
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        let milestone_id = res.events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_MILESTONE_ID)
+            .unwrap()
+            .value
+            .clone();
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+        let mut purchases = vec![];
+        for (buyer, paid) in [("alice", 100), ("bob", 50)] {
+            purchases.push(mock_buy(
+                deps.as_mut(),
+                buyer,
+                &project_id,
+                &offer_id,
+                &coins(paid, "orai"),
+            ));
+        }
+
+        let mut env = mock_env();
+        env.block.time = deadline;
+        let withdraw = ExecuteMsg::Withdraw {
+            project_id: project_id.clone(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("ciuz", &[]),
+            withdraw.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "ciuz".to_string(),
+                amount: coins(75, "orai"),
+            })
+        );
+        for (buyer, purchase_id) in ["alice", "bob"].iter().zip(purchases) {
+            let msg = ExecuteMsg::MarkShipped {
+                project_id: project_id.clone(),
+                purchase_id: purchase_id.clone(),
+                tracking_hash: None,
+            };
+            let _res = execute(deps.as_mut(), env.clone(), mock_info("ciuz", &[]), msg).unwrap();
+            let msg = ExecuteMsg::ConfirmDelivery {
+                project_id: project_id.clone(),
+                purchase_id,
+            };
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(buyer, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::AttestMilestone {
+            project_id: project_id.clone(),
+            milestone_id,
+            met: false,
+            evidence_uri: "ipfs://evidence".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("courier", &[]), msg).unwrap();
+        assert_eq!(res.events[1].attributes[0].value, ACTION_FAIL_MILESTONE);
+
+        // the failed tranche stays locked, and the project open to a cancel
+        let msg = QueryMsg::GetProject {
+            id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let project: ProjectResponse = from_binary(&res).unwrap();
+        assert_eq!(project.project.status, ProjectStatus::Delivering);
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("ciuz", &[]), withdraw).unwrap_err();
+        assert!(matches!(err, ContractError::NoFunds {}));
+
+        let msg = ExecuteMsg::ModerateProject {
+            id: project_id,
+            action: ModerationAction::Cancel,
+            reason: "milestone failed".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        // what the owner didn't withdraw goes back to the backers
+        let mut refunded = 0;
+        for (backer, refund) in [("alice", 50), ("bob", 25)] {
+            let res = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(backer, &[]),
+                ExecuteMsg::ClaimRefund {},
+            )
+            .unwrap();
+            assert_eq!(
+                res.messages[0].msg,
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: backer.to_string(),
+                    amount: coins(refund, "orai"),
+                })
+            );
+            refunded += refund;
+        }
+        assert_eq!(75 + refunded, 150);
+    }
+
+    #[test]
+    fn access_lists() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {};
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateAccessList {
+            list: AccessList::BlockedCreators,
+            add: vec!["spammer".to_string()],
+            remove: vec![],
+        };
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let create = ExecuteMsg::CreateProject {
+            metadata: "example".to_string(),
+        };
+        let info = mock_info("spammer", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, create.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::CreatorBlocked {}));
+
+        let msg = ExecuteMsg::SetVerifiedCreatorsOnly { enabled: true };
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("ciuz", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, create.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::CreatorNotAllowed {}));
+
+        let msg = ExecuteMsg::UpdateAccessList {
+            list: AccessList::AllowedCreators,
+            add: vec!["ciuz".to_string()],
+            remove: vec![],
+        };
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("ciuz", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, create).unwrap();
+        let project_id = res.attributes[1].clone().value;
+
+        let msg = ExecuteMsg::UpdateAccessList {
+            list: AccessList::BlockedBuyers,
+            add: vec!["backer".to_string()],
+            remove: vec![],
+        };
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::BuyOffer {
+            project_id,
+            offer_id: "offer".to_string(),
+            metadata: "example".to_string(),
+            rate: Uint128::zero(),
+            promo_code: None,
+            referrer: None,
+        };
+        let info = mock_info("backer", &coins(150, "orai"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::BuyerBlocked {}));
+
+        let msg = QueryMsg::GetAccess {};
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let access: AccessConfig = from_binary(&res).unwrap();
+        assert!(access.verified_creators_only);
+        assert_eq!(access.allowed_creators, vec![Addr::unchecked("ciuz")]);
+    }
+
+    #[test]
+    fn verify_creator() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {};
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::RegisterUser {};
+        let info = mock_info("ciuz", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::CreateProject {
+            metadata: "example".to_string(),
+        };
+        let info = mock_info("ciuz", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let project_id = res.attributes[1].clone().value;
+
+        let grant = ExecuteMsg::GrantVerification {
+            address: "ciuz".to_string(),
+            expire_at: Some(mock_env().block.time.plus_seconds(100).seconds()),
+        };
+        let info = mock_info("verifier", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, grant.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let msg = ExecuteMsg::AddVerifier {
+            address: "verifier".to_string(),
+        };
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("verifier", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, grant).unwrap();
+
+        let msg = QueryMsg::GetProject { id: project_id };
+        let res = query(deps.as_ref(), mock_env(), msg.clone()).unwrap();
+        let project: ProjectResponse = from_binary(&res).unwrap();
+        assert!(project.owner_verified);
+
+        // badge expires
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let project: ProjectResponse = from_binary(&res).unwrap();
+        assert!(!project.owner_verified);
+    }
+
+    #[test]
+    fn emit_events() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {};
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::CreateProject {
+            metadata: "example".to_string(),
+        };
+        let info = mock_info("ciuz", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let project_id = res.attributes[1].clone().value;
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty, EVENT_TYPE);
+
+        let msg = ExecuteMsg::LaunchProject {
+            id: project_id.clone(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        assert_eq!(res.events[0].attributes[0].value, ACTION_LAUNCH_PROJECT);
+
+        // nothing to average before the first purchase
+        let msg = ExecuteMsg::RateProject {
+            id: project_id.clone(),
+            rate: Uint128::from(4u128),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("backer", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PurchaseNotFound {}));
+
+        let msg = ExecuteMsg::CreateOffer {
+            id: project_id.clone(),
+            min_price: Uint128::from(100u128),
+            metadata: "example".to_string(),
+            expire_at: 0,
+        };
+        let info = mock_info("ciuz", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let offer_id = res.events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_OFFER_ID)
+            .unwrap()
+            .value
+            .clone();
+
+        let msg = ExecuteMsg::BuyOffer {
+            project_id: project_id.clone(),
+            offer_id: offer_id.clone(),
+            metadata: "example".to_string(),
+            rate: Uint128::zero(),
+            promo_code: None,
+            referrer: None,
+        };
+        let info = mock_info("backer", &coins(150, "orai"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let event = &res.events[0];
+        assert_eq!(event.ty, EVENT_TYPE);
+        let attr = |key: &str| {
+            event
+                .attributes
+                .iter()
+                .find(|attr| attr.key == key)
+                .map(|attr| attr.value.clone())
+        };
+        assert_eq!(attr(ATTR_ACTION), Some(ACTION_BUY_OFFER.to_string()));
+        assert_eq!(attr(ATTR_ACTOR), Some("backer".to_string()));
+        assert_eq!(attr(ATTR_PROJECT_ID), Some(project_id));
+        assert_eq!(attr(ATTR_OFFER_ID), Some(offer_id));
+        assert!(attr(ATTR_PURCHASE_ID).is_some());
+        assert_eq!(attr(ATTR_AMOUNT), Some("150".to_string()));
+        assert_eq!(attr(ATTR_DENOM), Some("orai".to_string()));
+        assert_eq!(attr(ATTR_OLD_VALUE), Some("0".to_string()));
+        assert_eq!(attr(ATTR_NEW_VALUE), Some("150".to_string()));
+    }
+
+    #[test]
+    fn funding_summary() {
+        let mut deps = setup();
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 100);
+        let other_id = mock_project(deps.as_mut(), "quan");
+
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateGoal {
+            id: project_id.clone(),
+            goal: Uint128::from(1000u128),
+            denom: Some("orai".to_string()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("quan", &[]),
+            msg.clone(),
+        );
+        assert!(matches!(err.unwrap_err(), ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+
+        mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(150, "orai"),
+        );
+        // only the goal's denom counts toward it
+        let msg = ExecuteMsg::BuyOffer {
+            project_id: project_id.clone(),
+            offer_id: offer_id.clone(),
+            metadata: "example".to_string(),
+            rate: Uint128::zero(),
+            promo_code: None,
+            referrer: None,
+        };
+        let info = mock_info("alice", &coins(100, "uatom"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFundingDenom { .. }));
+        mock_buy(
+            deps.as_mut(),
+            "bob",
+            &project_id,
+            &offer_id,
+            &coins(250, "orai"),
+        );
+
+        let msg = QueryMsg::GetFunding {
+            project_id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg.clone()).unwrap();
+        let funding: FundingResponse = from_binary(&res).unwrap();
+        assert_eq!(funding.goal_denom, Some("orai".to_string()));
+        assert_eq!(funding.total_pledged, Uint128::from(400u128));
+        assert_eq!(funding.raised, coins(400, "orai"));
+        assert_eq!(funding.backers, 2);
+        assert_eq!(funding.percent_funded, 40);
+        assert_eq!(funding.deadline, deadline);
+        assert_eq!(funding.status, FundingStatus::Active);
+
+        // goal missed once the deadline passes
+        let mut env = mock_env();
+        env.block.time = deadline;
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let funding: FundingResponse = from_binary(&res).unwrap();
+        assert_eq!(funding.status, FundingStatus::Failed);
+
+        let msg = QueryMsg::ListFunding {
+            status: Some(FundingStatus::Active),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let list: Vec<FundingResponse> = from_binary(&res).unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].project_id, other_id);
+
+        let msg = QueryMsg::ListFunding {
+            status: None,
+            start_after: Some(project_id),
+            limit: Some(1),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let list: Vec<FundingResponse> = from_binary(&res).unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].project_id, other_id);
+
+        let msg = QueryMsg::GetFunding {
+            project_id: "missing".to_string(),
+        };
+        let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+        assert!(matches!(err, StdError::NotFound { .. }));
+    }
+
+    #[test]
+    fn balances() {
+        let mut deps = setup();
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 100);
+
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateGoal {
+            id: project_id.clone(),
+            goal: Uint128::from(200u128),
+            denom: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+
+        mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(150, "orai"),
+        );
+        mock_buy(
+            deps.as_mut(),
+            "bob",
+            &project_id,
+            &offer_id,
+            &coins(100, "orai"),
+        );
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(260, "orai"));
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetBalance {}).unwrap();
+        let balance: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(balance.escrow, coins(250, "orai"));
+        assert_eq!(balance.surplus, coins(10, "orai"));
+        assert!(balance.shortfall.is_empty());
+
+        let msg = QueryMsg::GetProjectBalance {
+            project_id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg.clone()).unwrap();
+        let project_balance: ProjectBalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(project_balance.escrowed, coins(250, "orai"));
+        assert!(project_balance.withdrawable.is_empty());
+
+        let withdraw = ExecuteMsg::Withdraw {
+            project_id: project_id.clone(),
+        };
+        let info = mock_info("ciuz", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, withdraw.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::GoalNotReached {}));
+
+        let mut env = mock_env();
+        env.block.time = deadline;
+        let res = query(deps.as_ref(), env.clone(), msg.clone()).unwrap();
+        let project_balance: ProjectBalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(project_balance.withdrawable, coins(250, "orai"));
+
+        let info = mock_info("ciuz", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, withdraw).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "ciuz".to_string(),
+                amount: coins(250, "orai"),
+            })
+        );
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(10, "orai"));
+
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let project_balance: ProjectBalanceResponse = from_binary(&res).unwrap();
+        assert!(project_balance.escrowed.is_empty());
+        assert_eq!(project_balance.withdrawn, coins(250, "orai"));
+
+        let res = query(deps.as_ref(), env, QueryMsg::GetBalance {}).unwrap();
+        let balance: BalanceResponse = from_binary(&res).unwrap();
+        assert!(balance.escrow.is_empty());
+        assert_eq!(balance.surplus, coins(10, "orai"));
+
+        let msg = QueryMsg::GetUserBalance {
+            address: "alice".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let user_balance: UserBalanceResponse = from_binary(&res).unwrap();
+        assert!(user_balance.claimable_refunds.is_empty());
+    }
+
+    #[test]
+    fn contribution_ledger() {
+        let mut deps = setup();
+        let msg = ExecuteMsg::RegisterUser {};
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let project_id = mock_project(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 100);
+        let other_id = mock_project(deps.as_mut(), "quan");
+        let other_offer_id = mock_offer(deps.as_mut(), "quan", &other_id, 100);
+
+        mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(150, "orai"),
+        );
+        mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(100, "orai"),
+        );
+        mock_buy(
+            deps.as_mut(),
+            "bob",
+            &project_id,
+            &offer_id,
+            &coins(300, "orai"),
+        );
+        mock_buy(
+            deps.as_mut(),
+            "alice",
+            &other_id,
+            &other_offer_id,
+            &coins(100, "orai"),
+        );
+
+        let msg = QueryMsg::ListProjectContributions {
+            project_id: project_id.clone(),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let contributions: Vec<Contribution> = from_binary(&res).unwrap();
+        assert_eq!(contributions.len(), 2);
+        assert_eq!(contributions[0].backer, Addr::unchecked("bob"));
+        assert_eq!(contributions[1].backer, Addr::unchecked("alice"));
+        assert_eq!(contributions[1].amount, coins(250, "orai"));
+        assert_eq!(contributions[1].purchase_count, 2);
+
+        let msg = QueryMsg::ListProjectContributions {
+            project_id: project_id.clone(),
+            start_after: Some("bob".to_string()),
+            limit: Some(1),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let contributions: Vec<Contribution> = from_binary(&res).unwrap();
+        assert_eq!(contributions.len(), 1);
+        assert_eq!(contributions[0].backer, Addr::unchecked("alice"));
+
+        let msg = QueryMsg::ListBackerContributions {
+            backer: "alice".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let contributions: Vec<Contribution> = from_binary(&res).unwrap();
+        assert_eq!(contributions.len(), 2);
+
+        let msg = QueryMsg::GetUser {
+            id: Addr::unchecked("alice"),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let user: User = from_binary(&res).unwrap();
+        assert_eq!(user.total_spent, Uint128::from(350u128));
+        assert_eq!(user.project_funded.len(), 2);
+    }
+
+    #[test]
+    fn pledge() {
+        let mut deps = setup();
+        let project_id = mock_project(deps.as_mut(), "ciuz");
+
+        let msg = ExecuteMsg::UpdateMinPledge {
+            id: project_id.clone(),
+            min_pledge: Uint128::from(50u128),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Pledge {
+            project_id: project_id.clone(),
+        };
+        let info = mock_info("alice", &coins(10, "orai"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::PledgeTooLow { .. }));
+
+        let info = mock_info("alice", &coins(80, "orai"));
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = QueryMsg::GetFunding {
+            project_id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let funding: FundingResponse = from_binary(&res).unwrap();
+        assert_eq!(funding.total_pledged, Uint128::from(80u128));
+        assert_eq!(funding.raised, coins(80, "orai"));
+        assert_eq!(funding.backers, 1);
+
+        let msg = QueryMsg::ListProjectContributions {
+            project_id,
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let contributions: Vec<Contribution> = from_binary(&res).unwrap();
+        assert_eq!(contributions[0].amount, coins(80, "orai"));
+    }
+
+    #[test]
+    fn cancel_purchase() {
+        let mut deps = setup();
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 100);
+
+        let deadline = mock_env().block.time.seconds() + 1000;
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+        let msg = ExecuteMsg::UpdateCancellationPolicy {
+            id: project_id.clone(),
+            cutoff: Some(deadline - 100),
+            fee: Decimal::percent(10),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+
+        let purchase_id = mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(150, "orai"),
+        );
+
+        let msg = ExecuteMsg::CancelPurchase {
+            project_id: project_id.clone(),
+            purchase_id: purchase_id.clone(),
+            amount: Some(Uint128::from(40u128)),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alice".to_string(),
+                amount: coins(36, "orai"),
+            })
+        );
+
+        // can't go below the offer price
+        let msg = ExecuteMsg::CancelPurchase {
+            project_id: project_id.clone(),
+            purchase_id: purchase_id.clone(),
+            amount: Some(Uint128::from(20u128)),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg);
+        assert!(matches!(err.unwrap_err(), ContractError::InvalidAmount {}));
+
+        // only the buyer can cancel
+        let msg = ExecuteMsg::CancelPurchase {
+            project_id: project_id.clone(),
+            purchase_id: purchase_id.clone(),
+            amount: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            msg.clone(),
+        );
+        assert!(matches!(
+            err.unwrap_err(),
+            ContractError::PurchaseNotFound {}
+        ));
+
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(deadline - 100);
+        let err = execute(deps.as_mut(), env, mock_info("alice", &[]), msg.clone());
+        assert!(matches!(
+            err.unwrap_err(),
+            ContractError::CancellationClosed {}
+        ));
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alice".to_string(),
+                amount: coins(99, "orai"),
+            })
+        );
+
+        // the fees stay with the project
+        let msg = QueryMsg::GetFunding {
+            project_id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let funding: FundingResponse = from_binary(&res).unwrap();
+        assert_eq!(funding.total_pledged, Uint128::from(15u128));
+        assert_eq!(funding.raised, coins(15, "orai"));
+
+        let msg = QueryMsg::ListProjectContributions {
+            project_id: project_id.clone(),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let contributions: Vec<Contribution> = from_binary(&res).unwrap();
+        assert_eq!(contributions[0].amount, coins(15, "orai"));
+        assert_eq!(contributions[0].purchase_count, 0);
+
+        // purchases and pledges made in the same block are cancelled one at a time
+        let first = mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(100, "orai"),
+        );
+        let second = mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(100, "orai"),
+        );
+        assert_ne!(first, second);
+        let msg = ExecuteMsg::Pledge {
+            project_id: project_id.clone(),
+        };
+        let info = mock_info("alice", &coins(50, "orai"));
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::CancelPurchase {
+            project_id: project_id.clone(),
+            purchase_id: first,
+            amount: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let msg = QueryMsg::GetFunding { project_id };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let funding: FundingResponse = from_binary(&res).unwrap();
+        assert_eq!(funding.total_pledged, Uint128::from(175u128));
+    }
+
+    #[test]
+    fn subscription() {
+        let mut deps = setup();
+        let project_id = mock_project(deps.as_mut(), "ciuz");
+
+        let subscribe = ExecuteMsg::Subscribe {
+            project_id: project_id.clone(),
+            periods: 3,
+        };
+        let info = mock_info("alice", &coins(60, "orai"));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), subscribe.clone());
+        assert!(matches!(
+            err.unwrap_err(),
+            ContractError::NoSubscriptionPlan {}
+        ));
+
+        let msg = ExecuteMsg::UpdateSubscriptionPlan {
+            id: project_id.clone(),
+            period: 100,
+            min_price: Uint128::from(10u128),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), info, subscribe).unwrap();
+        let subscription_id = res.events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_PURCHASE_ID)
+            .unwrap()
+            .value
+            .clone();
+
+        let claim = ExecuteMsg::ClaimSubscription {
+            project_id: project_id.clone(),
+            subscription_id: subscription_id.clone(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("ciuz", &[]),
+            claim.clone(),
+        );
+        assert!(matches!(err.unwrap_err(), ContractError::NothingToClaim {}));
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(150);
+        let info = mock_info("ciuz", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), claim.clone()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "ciuz".to_string(),
+                amount: coins(20, "orai"),
+            })
+        );
+        let err = execute(deps.as_mut(), env.clone(), info, claim).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToClaim {}));
+
+        // the two periods not yet due go back to the backer
+        let msg = ExecuteMsg::CancelSubscription {
+            project_id: project_id.clone(),
+            subscription_id,
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alice".to_string(),
+                amount: coins(40, "orai"),
+            })
+        );
+
+        let msg = QueryMsg::ListSubscriptions {
+            project_id: project_id.clone(),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg.clone()).unwrap();
+        let subscriptions: Vec<Subscription> = from_binary(&res).unwrap();
+        assert_eq!(subscriptions[0].periods, 1);
+        assert_eq!(subscriptions[0].claimed, 1);
+        assert!(subscriptions[0].cancelled);
+
+        // a second subscription in the same block doesn't overwrite the first
+        let subscribe = ExecuteMsg::Subscribe {
+            project_id,
+            periods: 2,
+        };
+        let info = mock_info("bob", &coins(20, "orai"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), subscribe.clone()).unwrap();
+        let _res = execute(deps.as_mut(), env.clone(), info, subscribe).unwrap();
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let subscriptions: Vec<Subscription> = from_binary(&res).unwrap();
+        assert_eq!(subscriptions.len(), 3);
+    }
+
+    #[test]
+    fn stretch_goals() {
+        let mut deps = setup();
+        let project_id = mock_project(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 100);
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(1);
+        let msg = ExecuteMsg::CreateOffer {
+            id: project_id.clone(),
+            min_price: Uint128::from(100u128),
+            metadata: "deluxe".to_string(),
+            expire_at: 0,
+        };
+        let res = execute(deps.as_mut(), env, mock_info("ciuz", &[]), msg).unwrap();
+        let deluxe_id = res.events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_OFFER_ID)
+            .unwrap()
+            .value
+            .clone();
+
+        let msg = ExecuteMsg::AddStretchGoal {
+            id: project_id.clone(),
+            threshold: Uint128::from(150u128),
+            offer_ids: vec![deluxe_id.clone()],
+            metadata: "bonus sensor".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::BuyOffer {
+            project_id: project_id.clone(),
+            offer_id: deluxe_id.clone(),
+            metadata: "example".to_string(),
+            rate: Uint128::zero(),
+            promo_code: None,
+            referrer: None,
+        };
+        let info = mock_info("bob", &coins(100, "orai"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::OfferInactive {}));
+
+        mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(100, "orai"),
+        );
+        let msg = ExecuteMsg::Pledge {
+            project_id: project_id.clone(),
+        };
+        let info = mock_info("carol", &coins(60, "orai"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert!(res.events.iter().any(|event| event
+            .attributes
+            .iter()
+            .any(|attr| attr.value == ACTION_STRETCH_GOAL_REACHED)));
+
+        mock_buy(
+            deps.as_mut(),
+            "bob",
+            &project_id,
+            &deluxe_id,
+            &coins(100, "orai"),
+        );
+
+        let msg = QueryMsg::GetStretchGoals {
+            project_id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let goals: Vec<StretchGoal> = from_binary(&res).unwrap();
+        assert_eq!(goals[0].reached_at, Some(mock_env().block.time));
+
+        let msg = QueryMsg::GetProject { id: project_id };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let project: ProjectResponse = from_binary(&res).unwrap();
+        assert!(project.project.offers.iter().all(|offer| offer.active));
+        assert!(project
+            .project
+            .bougth_offers
+            .iter()
+            .all(|offer| offer.extras == vec!["bonus sensor".to_string()]));
+    }
+
+    #[test]
+    fn shared_stretch_goals() {
+        let mut deps = setup();
+        let project_id = mock_project(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 100);
+
+        // goals added in the same block, all gating the offer
+        let mut goal_ids = vec![];
+        for threshold in [100u128, 1000, 2000] {
+            let msg = ExecuteMsg::AddStretchGoal {
+                id: project_id.clone(),
+                threshold: Uint128::from(threshold),
+                offer_ids: vec![offer_id.clone()],
+                metadata: "bonus sensor".to_string(),
+            };
+            let res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+            let goal_id = res.events[0]
+                .attributes
+                .iter()
+                .find(|attr| attr.key == ATTR_STRETCH_GOAL_ID)
+                .unwrap()
+                .value
+                .clone();
+            goal_ids.push(goal_id);
+        }
+        assert_ne!(goal_ids[0], goal_ids[1]);
+
+        let buy = ExecuteMsg::BuyOffer {
+            project_id: project_id.clone(),
+            offer_id,
+            metadata: "example".to_string(),
+            rate: Uint128::zero(),
+            promo_code: None,
+            referrer: None,
+        };
+        let info = mock_info("bob", &coins(100, "orai"));
+
+        // reaching the first goal leaves the offer gated by the others
+        let msg = ExecuteMsg::Pledge {
+            project_id: project_id.clone(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("carol", &coins(150, "orai")),
+            msg,
+        )
+        .unwrap();
+        let reached = res.events[1]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_STRETCH_GOAL_ID)
+            .map(|attr| attr.value.clone());
+        assert_eq!(reached, Some(goal_ids[0].clone()));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), buy.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::OfferInactive {}));
+
+        // and removing one unmet goal leaves it gated by the last
+        let remove = |goal_id: &String| ExecuteMsg::RemoveStretchGoal {
+            id: project_id.clone(),
+            goal_id: goal_id.clone(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("ciuz", &[]),
+            remove(&goal_ids[1]),
+        )
+        .unwrap();
+        let removed = res.events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_STRETCH_GOAL_ID)
+            .map(|attr| attr.value.clone());
+        assert_eq!(removed, Some(goal_ids[1].clone()));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), buy.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::OfferInactive {}));
+
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("ciuz", &[]),
+            remove(&goal_ids[2]),
+        )
+        .unwrap();
+        let _res = execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+    }
+
+    #[test]
+    fn promo_codes() {
+        let mut deps = setup();
+        let project_id = mock_project(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 100);
+
+        let msg = ExecuteMsg::AddPromoCode {
+            id: project_id.clone(),
+            code_hash: hash_code("INFLUENCER"),
+            discount: Discount::Percent(Decimal::percent(20)),
+            max_uses: 1,
+            expire_at: Some(mock_env().block.time.seconds() + 1000),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+
+        let buy = |code: &str| ExecuteMsg::BuyOffer {
+            project_id: project_id.clone(),
+            offer_id: offer_id.clone(),
+            metadata: "example".to_string(),
+            rate: Uint128::zero(),
+            promo_code: Some(code.to_string()),
+            referrer: None,
+        };
+        let info = mock_info("alice", &coins(90, "orai"));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), buy("WRONG")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPromoCode {}));
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(1000);
+        let err = execute(deps.as_mut(), env, info.clone(), buy("INFLUENCER")).unwrap_err();
+        assert!(matches!(err, ContractError::PromoCodeExpired {}));
+
+        let res = execute(deps.as_mut(), mock_env(), info, buy("INFLUENCER")).unwrap();
+        let purchase_id = res.events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_PURCHASE_ID)
+            .unwrap()
+            .value
+            .clone();
+        let info = mock_info("bob", &coins(80, "orai"));
+        let err = execute(deps.as_mut(), mock_env(), info, buy("INFLUENCER")).unwrap_err();
+        assert!(matches!(err, ContractError::PromoCodeExhausted {}));
+
+        let msg = QueryMsg::ListPromoCodes {
+            project_id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let promos: Vec<PromoCode> = from_binary(&res).unwrap();
+        assert_eq!(promos[0].used, 1);
+
+        // the overpayment can be taken back down to the discounted price
+        let cancel = |amount: u128| ExecuteMsg::CancelPurchase {
+            project_id: project_id.clone(),
+            purchase_id: purchase_id.clone(),
+            amount: Some(Uint128::from(amount)),
+        };
+        let info = mock_info("alice", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), cancel(10)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info, cancel(1)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAmount {}));
+        let msg = QueryMsg::GetReputation {
+            owner: "ciuz".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let reputation: ReputationResponse = from_binary(&res).unwrap();
+        assert_eq!(reputation.reputation.refunds, 0);
+
+        let msg = QueryMsg::GetProject { id: project_id };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let project: ProjectResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            project.project.bougth_offers[0].promo_code,
+            Some(hash_code("INFLUENCER"))
+        );
+    }
+
+    #[test]
+    fn referral_rewards() {
+        let mut deps = setup();
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 100);
+
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+        let msg = ExecuteMsg::UpdateReferralShare {
+            id: project_id.clone(),
+            share: Decimal::percent(10),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+
+        let buy = |referrer: &str| ExecuteMsg::BuyOffer {
+            project_id: project_id.clone(),
+            offer_id: offer_id.clone(),
+            metadata: "example".to_string(),
+            rate: Uint128::zero(),
+            promo_code: None,
+            referrer: Some(referrer.to_string()),
+        };
+        let info = mock_info("alice", &coins(200, "orai"));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), buy("alice")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidReferrer {}));
+        let _res = execute(deps.as_mut(), mock_env(), info, buy("bob")).unwrap();
+
+        let msg = QueryMsg::GetReferralStats {
+            address: "bob".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg.clone()).unwrap();
+        let stats: ReferralStatsResponse = from_binary(&res).unwrap();
+        assert_eq!(stats.referrals, 1);
+        assert_eq!(stats.volume, coins(200, "orai"));
+        assert_eq!(stats.pending, coins(20, "orai"));
+        assert!(stats.claimable.is_empty());
+
+        let mut env = mock_env();
+        env.block.time = deadline;
+        let withdraw = ExecuteMsg::Withdraw {
+            project_id: project_id.clone(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("ciuz", &[]), withdraw).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "ciuz".to_string(),
+                amount: coins(180, "orai"),
+            })
+        );
+
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let stats: ReferralStatsResponse = from_binary(&res).unwrap();
+        assert!(stats.pending.is_empty());
+        assert_eq!(stats.earned, coins(20, "orai"));
+        assert_eq!(stats.claimable, coins(20, "orai"));
+
+        let msg = ExecuteMsg::ClaimReferralRewards {};
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("bob", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "bob".to_string(),
+                amount: coins(20, "orai"),
+            })
+        );
+        let err = execute(deps.as_mut(), env, mock_info("bob", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToClaim {}));
+    }
+
+    #[test]
+    fn deferred_referral_rewards() {
+        let mut deps = setup();
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 100);
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        // half of the proceeds stay locked behind two milestones added together
+        for metadata in ["prototype", "production"] {
+            let msg = ExecuteMsg::AddMilestone {
+                id: project_id.clone(),
+                metadata: metadata.to_string(),
+                tranche: Decimal::percent(25),
+                condition: MilestoneCondition::Oracle { quorum: 1 },
+            };
+            let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        }
+        let msg = QueryMsg::GetMilestones {
+            project_id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let milestones: Vec<Milestone> = from_binary(&res).unwrap();
+        assert_eq!(milestones.len(), 2);
+        assert_ne!(milestones[0].id, milestones[1].id);
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+        let msg = ExecuteMsg::UpdateReferralShare {
+            id: project_id.clone(),
+            share: Decimal::percent(60),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        for buyer in ["alice", "bob"] {
+            let msg = ExecuteMsg::BuyOffer {
+                project_id: project_id.clone(),
+                offer_id: offer_id.clone(),
+                metadata: "example".to_string(),
+                rate: Uint128::zero(),
+                promo_code: None,
+                referrer: Some("carol".to_string()),
+            };
+            let info = mock_info(buyer, &coins(100, "orai"));
+            let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        // 100 is unlocked, enough for one 60 reward, the other waits for the milestone
+        let mut env = mock_env();
+        env.block.time = deadline;
+        let msg = QueryMsg::GetProjectBalance {
+            project_id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let balance: ProjectBalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(balance.withdrawable, coins(40, "orai"));
+
+        let msg = ExecuteMsg::Withdraw { project_id };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("ciuz", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "ciuz".to_string(),
+                amount: coins(40, "orai"),
+            })
+        );
+
+        let msg = QueryMsg::GetReferralStats {
+            address: "carol".to_string(),
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let stats: ReferralStatsResponse = from_binary(&res).unwrap();
+        assert_eq!(stats.claimable, coins(60, "orai"));
+        assert_eq!(stats.pending, coins(60, "orai"));
+    }
+
+    #[test]
+    fn device_registry() {
+        let mut deps = setup();
+        let project_id = mock_project(deps.as_mut(), "ciuz");
+
+        let register = |device_id: &str, pubkey: &[u8]| ExecuteMsg::RegisterDevice {
+            project_id: project_id.clone(),
+            device_id: device_id.to_string(),
+            pubkey: Binary::from(pubkey),
+            key_type: KeyType::Ed25519,
+            model: "sensor-v1".to_string(),
+        };
+        let info = mock_info("ciuz", &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            register("d1", &[1; 33]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPublicKey {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            register("d1", &[1; 32]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        for device_id in ["d1", "d2", "d3"] {
+            let _res = execute(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                register(device_id, &[1; 32]),
+            )
+            .unwrap();
+        }
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            register("d1", &[1; 32]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DeviceAlreadyExists {}));
+
+        let msg = ExecuteMsg::RotateDeviceKey {
+            project_id: project_id.clone(),
+            device_id: "d1".to_string(),
+            pubkey: Binary::from([2; 33]),
+            key_type: KeyType::Secp256k1,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::RetireDevice {
+            project_id: project_id.clone(),
+            device_id: "d2".to_string(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::DeviceRetired {}));
+
+        let msg = QueryMsg::GetDevice {
+            project_id: project_id.clone(),
+            device_id: "d1".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let device: Device = from_binary(&res).unwrap();
+        assert_eq!(device.key_type, KeyType::Secp256k1);
+        assert_eq!(device.pubkey, Binary::from([2; 33]));
+
+        let msg = QueryMsg::ListDevices {
+            project_id,
+            start_after: Some("d1".to_string()),
+            limit: Some(1),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let devices: Vec<Device> = from_binary(&res).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].id, "d2");
+        assert_eq!(devices[0].status, DeviceStatus::Retired);
+    }
+
+    #[test]
+    fn submit_telemetry() {
+        let mut deps = setup();
+        let project_id = mock_project(deps.as_mut(), "ciuz");
+        let signing_key = SigningKey::from([7u8; 32]);
+        let pubkey: [u8; 32] = VerificationKey::from(&signing_key).into();
+
+        let msg = ExecuteMsg::RegisterDevice {
+            project_id: project_id.clone(),
+            device_id: "d1".to_string(),
+            pubkey: Binary::from(pubkey),
+            key_type: KeyType::Ed25519,
+            model: "sensor-v1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+
+        let payload_hash = hash_code("temperature=21.5");
+        let telemetry = |counter: u64, signed_counter: u64| {
+            let digest = telemetry_digest(&project_id, "d1", &payload_hash, signed_counter);
+            let signature: [u8; 64] = signing_key.sign(&digest).into();
+            ExecuteMsg::SubmitTelemetry {
+                project_id: project_id.clone(),
+                device_id: "d1".to_string(),
+                payload_hash: payload_hash.clone(),
+                counter,
+                signature: Binary::from(signature),
+            }
+        };
+        let info = mock_info("relayer", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), telemetry(2, 1)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature {}));
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), telemetry(1, 1)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), telemetry(1, 1)).unwrap_err();
+        assert!(matches!(err, ContractError::StaleCounter { last: 1 }));
+        let _res = execute(deps.as_mut(), mock_env(), info, telemetry(5, 5)).unwrap();
+
+        let msg = QueryMsg::GetAttestation {
+            project_id: project_id.clone(),
+            device_id: "d1".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let attestation: Option<Attestation> = from_binary(&res).unwrap();
+        let attestation = attestation.unwrap();
+        assert_eq!(attestation.counter, 5);
+        assert_eq!(attestation.payload_hash, payload_hash);
+        assert_eq!(attestation.submitter, Addr::unchecked("relayer"));
+    }
+
+    #[test]
+    fn telemetry_milestone() {
+        let mut deps = setup();
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 100);
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+        mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(100, "orai"),
+        );
+
+        let first = mock_device(deps.as_mut(), "ciuz", &project_id, "d1");
+        let second = mock_device(deps.as_mut(), "ciuz", &project_id, "d2");
+        let mut env = mock_env();
+        env.block.time = deadline;
+        // an attestation from before the milestone window doesn't count
+        mock_telemetry(deps.as_mut(), mock_env(), &project_id, "d1", &first, 1);
+
+        let msg = ExecuteMsg::AddMilestone {
+            id: project_id.clone(),
+            metadata: "prototype online".to_string(),
+            tranche: Decimal::percent(60),
+            condition: MilestoneCondition::Telemetry {
+                min_devices: 2,
+                after: deadline,
+            },
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+
+        let withdraw = ExecuteMsg::Withdraw {
+            project_id: project_id.clone(),
+        };
+        let info = mock_info("ciuz", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), withdraw.clone()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "ciuz".to_string(),
+                amount: coins(40, "orai"),
+            })
+        );
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), withdraw.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NoFunds {}));
+
+        let res = mock_telemetry(deps.as_mut(), env.clone(), &project_id, "d1", &first, 2);
+        assert_eq!(res.events.len(), 1);
+        let res = mock_telemetry(deps.as_mut(), env.clone(), &project_id, "d2", &second, 1);
+        assert!(res.events.iter().any(|event| event
+            .attributes
+            .iter()
+            .any(|attr| attr.value == ACTION_COMPLETE_MILESTONE)));
+
+        let msg = QueryMsg::GetMilestones {
+            project_id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let milestones: Vec<Milestone> = from_binary(&res).unwrap();
+        assert_eq!(milestones[0].completed_at, Some(deadline));
+
+        let res = execute(deps.as_mut(), env, info, withdraw).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "ciuz".to_string(),
+                amount: coins(60, "orai"),
+            })
+        );
+    }
+
+    #[test]
+    fn oracle_milestone() {
+        let mut deps = setup();
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+
+        let msg = ExecuteMsg::AddOracle {
+            address: "shipper".to_string(),
+            project_id: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        for oracle in ["courier", "auditor"] {
+            let msg = ExecuteMsg::AddOracle {
+                address: oracle.to_string(),
+                project_id: Some(project_id.clone()),
+            };
+            let _res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        }
+        let msg = QueryMsg::ListOracles {
+            project_id: Some(project_id.clone()),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let oracles: Vec<Addr> = from_binary(&res).unwrap();
+        assert_eq!(oracles.len(), 2);
+
+        let msg = ExecuteMsg::AddMilestone {
+            id: project_id.clone(),
+            metadata: "units shipped".to_string(),
+            tranche: Decimal::percent(50),
+            condition: MilestoneCondition::Oracle { quorum: 2 },
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        let milestone_id = res.events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_MILESTONE_ID)
+            .unwrap()
+            .value
+            .clone();
+        // oracles only settle milestones once the campaign is funded
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+        let mut env = mock_env();
+        env.block.time = deadline;
+
+        let attest = |met: bool| ExecuteMsg::AttestMilestone {
+            project_id: project_id.clone(),
+            milestone_id: milestone_id.clone(),
+            met,
+            evidence_uri: "ipfs://evidence".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("shipper", &[]),
+            attest(true),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidProjectStatus {
+                status: ProjectStatus::Live
+            }
+        ));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("ciuz", &[]),
+            attest(true),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("shipper", &[]),
+            attest(true),
+        )
+        .unwrap();
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("courier", &[]),
+            attest(false),
+        )
+        .unwrap();
+        // the auditor breaks the tie
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("auditor", &[]),
+            attest(true),
+        )
+        .unwrap();
+        for event in res.events.iter() {
+            let attested = event
+                .attributes
+                .iter()
+                .find(|attr| attr.key == ATTR_MILESTONE_ID)
+                .map(|attr| attr.value.clone());
+            assert_eq!(attested, Some(milestone_id.clone()));
+        }
+        assert_eq!(res.events[1].attributes[0].value, ACTION_COMPLETE_MILESTONE);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("courier", &[]),
+            attest(true),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MilestoneSettled {}));
+
+        let msg = QueryMsg::GetMilestones { project_id };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let milestones: Vec<Milestone> = from_binary(&res).unwrap();
+        assert_eq!(milestones[0].completed_at, Some(deadline));
+        assert_eq!(milestones[0].votes.len(), 3);
+    }
+
+    #[test]
+    fn disputes() {
+        let mut deps = setup();
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 50);
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+        let purchase_id = mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(100, "orai"),
+        );
+        mock_buy(
+            deps.as_mut(),
+            "bob",
+            &project_id,
+            &offer_id,
+            &coins(50, "orai"),
+        );
+
+        let msg = ExecuteMsg::OpenDispute {
+            project_id: project_id.clone(),
+            purchase_id: purchase_id.clone(),
+            reason: "never shipped".to_string(),
+            evidence_uri: "ipfs://evidence".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PurchaseNotFound {}));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        let dispute_id = res.events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_DISPUTE_ID)
+            .unwrap()
+            .value
+            .clone();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::DisputeAlreadyOpen {}));
+
+        // the disputed purchase stays in escrow
+        let mut env = mock_env();
+        env.block.time = deadline;
+        let withdraw = ExecuteMsg::Withdraw {
+            project_id: project_id.clone(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("ciuz", &[]),
+            withdraw.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "ciuz".to_string(),
+                amount: coins(50, "orai"),
+            })
+        );
+
+        let msg = ExecuteMsg::AddArbitrator {
+            address: "judge".to_string(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let resolve = ExecuteMsg::ResolveDispute {
+            dispute_id: dispute_id.clone(),
+            resolution: DisputeResolution::PartialRefund {
+                amount: Uint128::from(40u128),
+            },
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("ciuz", &[]),
+            resolve.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("judge", &[]),
+            resolve.clone(),
+        )
+        .unwrap();
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("judge", &[]), resolve).unwrap_err();
+        assert!(matches!(err, ContractError::DisputeResolved {}));
+
+        let msg = QueryMsg::GetUserBalance {
+            address: "alice".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let balance: UserBalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(balance.claimable_refunds, coins(40, "orai"));
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info("ciuz", &[]), withdraw).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "ciuz".to_string(),
+                amount: coins(60, "orai"),
+            })
+        );
+
+        let msg = QueryMsg::ListDisputes {
+            project_id: Some(project_id),
+            status: Some(DisputeStatus::PartiallyRefunded),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let disputes: Vec<Dispute> = from_binary(&res).unwrap();
+        assert_eq!(disputes.len(), 1);
+        assert_eq!(disputes[0].refunded, Uint128::from(40u128));
+        assert_eq!(disputes[0].resolver, Some(Addr::unchecked("judge")));
+    }
+
+    #[test]
+    fn cancel_with_open_disputes() {
+        let mut deps = setup();
+        let project_id = mock_project(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 50);
+        let mut dispute_ids = vec![];
+        for buyer in ["alice", "bob"] {
+            let purchase_id = mock_buy(
+                deps.as_mut(),
+                buyer,
+                &project_id,
+                &offer_id,
+                &coins(100, "orai"),
+            );
+            let msg = ExecuteMsg::OpenDispute {
+                project_id: project_id.clone(),
+                purchase_id,
+                reason: "never shipped".to_string(),
+                evidence_uri: "ipfs://evidence".to_string(),
+            };
+            let res = execute(deps.as_mut(), mock_env(), mock_info(buyer, &[]), msg).unwrap();
+            let dispute_id = res.events[0]
+                .attributes
+                .iter()
+                .find(|attr| attr.key == ATTR_DISPUTE_ID)
+                .unwrap()
+                .value
+                .clone();
+            dispute_ids.push(dispute_id);
+        }
+        // disputes opened in the same block don't share an id
+        assert_ne!(dispute_ids[0], dispute_ids[1]);
+
+        let msg = ExecuteMsg::ModerateProject {
+            id: project_id.clone(),
+            action: ModerationAction::Cancel,
+            reason: "fraud".to_string(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // the cancel already refunded the disputed purchases
+        let msg = ExecuteMsg::ResolveDispute {
+            dispute_id: dispute_ids[0].clone(),
+            resolution: DisputeResolution::Refund,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::DisputeResolved {}));
+        let msg = QueryMsg::GetUserBalance {
+            address: "alice".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let balance: UserBalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(balance.claimable_refunds, coins(100, "orai"));
+
+        let msg = QueryMsg::ListDisputes {
+            project_id: Some(project_id),
+            status: Some(DisputeStatus::Closed),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let disputes: Vec<Dispute> = from_binary(&res).unwrap();
+        assert_eq!(disputes.len(), 2);
+    }
+
+    #[test]
+    fn fulfillment() {
+        let mut deps = setup();
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 50);
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+        let mut purchases = vec![];
+        for buyer in ["alice", "bob", "carol"] {
+            purchases.push(mock_buy(
+                deps.as_mut(),
+                buyer,
+                &project_id,
+                &offer_id,
+                &coins(50, "orai"),
+            ));
+        }
+
+        let confirm = ExecuteMsg::ConfirmDelivery {
+            project_id: project_id.clone(),
+            purchase_id: purchases[0].clone(),
+        };
+        let info = mock_info("alice", &[]);
+        let mut env = mock_env();
+        env.block.time = deadline;
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), confirm.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidProjectStatus {
+                status: ProjectStatus::Funded
+            }
+        ));
+
+        for purchase_id in purchases.iter().take(2) {
+            let msg = ExecuteMsg::MarkShipped {
+                project_id: project_id.clone(),
+                purchase_id: purchase_id.clone(),
+                tracking_hash: Some(hash_code("TRACK-123")),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), mock_info("ciuz", &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::ConfirmDelivery {
+            project_id: project_id.clone(),
+            purchase_id: purchases[2].clone(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("carol", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NotShipped {}));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), confirm.clone()).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info, confirm).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyConfirmed {}));
+
+        let msg = QueryMsg::GetProject {
+            id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg.clone()).unwrap();
+        let project: ProjectResponse = from_binary(&res).unwrap();
+        assert_eq!(project.fulfillment.shipped, 2);
+        assert_eq!(project.fulfillment.delivered, 1);
+
+        // bob never confirms
+        env.block.time = env.block.time.plus_seconds(AUTO_CONFIRM_PERIOD);
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let project: ProjectResponse = from_binary(&res).unwrap();
+        assert_eq!(project.fulfillment.delivered, 2);
+        assert_eq!(
+            project.fulfillment.fulfillment_rate,
+            Decimal::from_ratio(2u32, 3u32)
+        );
+    }
+
+    #[test]
+    fn reputation() {
+        let mut deps = setup();
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 50);
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+        let first = mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(50, "orai"),
+        );
+        let second = mock_buy(
+            deps.as_mut(),
+            "bob",
+            &project_id,
+            &offer_id,
+            &coins(50, "orai"),
+        );
+
+        let msg = ExecuteMsg::RateOffer {
+            project_id: project_id.clone(),
+            offer_id: first.clone(),
+            rate: Uint128::from(4u128),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PurchaseNotFound {}));
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::CancelPurchase {
+            project_id: project_id.clone(),
+            purchase_id: second,
+            amount: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = deadline;
+        let msg = ExecuteMsg::MarkShipped {
+            project_id: project_id.clone(),
+            purchase_id: first.clone(),
+            tracking_hash: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info("ciuz", &[]), msg).unwrap();
+        let msg = ExecuteMsg::ConfirmDelivery {
+            project_id: project_id.clone(),
+            purchase_id: first,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Withdraw { project_id };
+        let _res = execute(deps.as_mut(), env, mock_info("ciuz", &[]), msg).unwrap();
+
+        let msg = QueryMsg::GetReputation {
+            owner: "ciuz".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let reputation: ReputationResponse = from_binary(&res).unwrap();
+        assert_eq!(reputation.reputation.campaigns, 1);
+        assert_eq!(reputation.reputation.completed_campaigns, 1);
+        assert_eq!(reputation.reputation.backings, 2);
+        assert_eq!(reputation.reputation.refunds, 1);
+        assert_eq!(reputation.reputation.confirmed_deliveries, 1);
+        assert_eq!(reputation.avg_rating, Uint128::from(4u128));
+        // completion 1, non-refund 1/2, confirmation 1
+        assert_eq!(
+            reputation.score,
+            Some(Decimal::percent(250) / Decimal::percent(300))
+        );
+
+        // a campaign that misses its goal counts as failed once its deadline passes
+        let project_id = mock_draft(deps.as_mut(), "dave");
+        let msg = ExecuteMsg::UpdateGoal {
+            id: project_id.clone(),
+            goal: Uint128::from(100u128),
+            denom: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("dave", &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("dave", &[]), msg).unwrap();
+        mock_launch(deps.as_mut(), "dave", &project_id);
+        let failed_campaigns = |deps: Deps, env: Env| {
+            let msg = QueryMsg::GetReputation {
+                owner: "dave".to_string(),
+            };
+            let res = query(deps, env, msg).unwrap();
+            let reputation: ReputationResponse = from_binary(&res).unwrap();
+            reputation.reputation.failed_campaigns
+        };
+        let mut env = mock_env();
+        assert_eq!(failed_campaigns(deps.as_ref(), env.clone()), 0);
+        env.block.time = deadline;
+        assert_eq!(failed_campaigns(deps.as_ref(), env.clone()), 1);
+
+        let msg = ExecuteMsg::ModerateProject {
+            id: project_id,
+            action: ModerationAction::Cancel,
+            reason: "abandoned".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(failed_campaigns(deps.as_ref(), env), 1);
+    }
+
+    #[test]
+    fn collaborators() {
+        let mut deps = setup();
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+        for (address, roles) in [
+            ("editor", vec![Role::Editor]),
+            ("treasurer", vec![Role::Treasurer]),
+            ("reporter", vec![Role::Reporter]),
+        ] {
+            let msg = ExecuteMsg::SetCollaborator {
+                id: project_id.clone(),
+                address: address.to_string(),
+                roles,
+            };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(address, &[]),
+                msg.clone(),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::Unauthorized {}));
+            let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        }
+
+        // events carry the previous and new roles
+        for (roles, old_roles, new_roles) in [
+            (vec![Role::Reporter, Role::Editor], "", "reporter,editor"),
+            (vec![], "reporter,editor", ""),
+        ] {
+            let msg = ExecuteMsg::SetCollaborator {
+                id: project_id.clone(),
+                address: "helper".to_string(),
+                roles,
+            };
+            let res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+            let attr = |key: &str| {
+                res.events[0]
+                    .attributes
+                    .iter()
+                    .find(|attr| attr.key == key)
+                    .map(|attr| attr.value.clone())
+            };
+            assert_eq!(attr(ATTR_COLLABORATOR), Some("helper".to_string()));
+            assert_eq!(attr(ATTR_OLD_VALUE), Some(old_roles.to_string()));
+            assert_eq!(attr(ATTR_NEW_VALUE), Some(new_roles.to_string()));
+        }
+
+        // editor manages offers and campaign settings, the reporter can't
+        let offer_id = mock_offer(deps.as_mut(), "editor", &project_id, 50);
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("reporter", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("editor", &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateMinPledge {
+            id: project_id.clone(),
+            min_pledge: Uint128::from(10u128),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("editor", &[]), msg).unwrap();
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+        let msg = ExecuteMsg::CreateOffer {
+            id: project_id.clone(),
+            min_price: Uint128::from(10u128),
+            metadata: "example".to_string(),
+            expire_at: 0,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("reporter", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // reporter registers devices and ships
+        mock_device(deps.as_mut(), "reporter", &project_id, "device-1");
+        let purchase_id = mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(50, "orai"),
+        );
+        let mut env = mock_env();
+        env.block.time = deadline;
+        let msg = ExecuteMsg::MarkShipped {
+            project_id: project_id.clone(),
+            purchase_id,
+            tracking_hash: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info("reporter", &[]), msg).unwrap();
+
+        // treasurer withdraws, funds still go to the owner
+        let msg = ExecuteMsg::Withdraw {
+            project_id: project_id.clone(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("editor", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), env, mock_info("treasurer", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "ciuz".to_string(),
+                amount: coins(50, "orai"),
+            })
+        );
+
+        // empty roles remove the collaborator
+        let msg = ExecuteMsg::SetCollaborator {
+            id: project_id.clone(),
+            address: "editor".to_string(),
+            roles: vec![],
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        let msg = ExecuteMsg::DeleteOffer {
+            id: project_id,
+            offer_id,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("editor", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn ownership_transfer() {
+        let mut deps = setup();
+        for address in ["ciuz", "bob"] {
+            let msg = ExecuteMsg::RegisterUser {};
+            let _res = execute(deps.as_mut(), mock_env(), mock_info(address, &[]), msg).unwrap();
+        }
+        let project_id = mock_draft(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 50);
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: project_id.clone(),
+            timestamp: deadline.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        mock_launch(deps.as_mut(), "ciuz", &project_id);
+        let purchase_id = mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(50, "orai"),
+        );
+        let rate = |rate: u128| ExecuteMsg::RateOffer {
+            project_id: project_id.clone(),
+            offer_id: purchase_id.clone(),
+            rate: Uint128::from(rate),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), rate(4)).unwrap();
+        let msg = ExecuteMsg::OpenDispute {
+            project_id: project_id.clone(),
+            purchase_id: purchase_id.clone(),
+            reason: "never shipped".to_string(),
+            evidence_uri: "ipfs://evidence".to_string(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let dispute_id = res.events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key == ATTR_DISPUTE_ID)
+            .unwrap()
+            .value
+            .clone();
+
+        let msg = ExecuteMsg::ProposeOwnershipTransfer {
+            id: project_id.clone(),
+            new_owner: Some("bob".to_string()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        let msg = ExecuteMsg::ProposeOwnershipTransfer {
+            id: project_id.clone(),
+            new_owner: Some("ciuz".to_string()),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidNewOwner {}));
+
+        // only the proposed address can accept, and the owner is unchanged until then
+        let msg = ExecuteMsg::AcceptOwnershipTransfer {
+            id: project_id.clone(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let msg = QueryMsg::GetProject {
+            id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let project: ProjectResponse = from_binary(&res).unwrap();
+        assert_eq!(project.project.owner, Addr::unchecked("bob"));
+        assert_eq!(project.project.pending_owner, None);
+        for (address, owned) in [("ciuz", vec![]), ("bob", vec![project_id.clone()])] {
+            let msg = QueryMsg::GetUser {
+                id: Addr::unchecked(address),
+            };
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            let user: User = from_binary(&res).unwrap();
+            assert_eq!(user.project_owned, owned);
+        }
+        // the project's record moves too, so later updates find it on the new owner
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), rate(0)).unwrap();
+        let msg = ExecuteMsg::ResolveDispute {
+            dispute_id,
+            resolution: DisputeResolution::PartialRefund {
+                amount: Uint128::from(10u128),
+            },
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        for (address, campaigns, upheld) in [("ciuz", 0, 0), ("bob", 1, 1)] {
+            let msg = QueryMsg::GetReputation {
+                owner: address.to_string(),
+            };
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            let reputation: ReputationResponse = from_binary(&res).unwrap();
+            assert_eq!(reputation.reputation.campaigns, campaigns);
+            assert_eq!(reputation.reputation.backings, campaigns);
+            assert_eq!(reputation.reputation.disputes, campaigns);
+            assert_eq!(reputation.reputation.disputes_upheld, upheld);
+            assert_eq!(reputation.reputation.rating_count, 0);
+        }
+
+        // the pending proceeds now go to the new owner
+        let mut env = mock_env();
+        env.block.time = deadline;
+        let msg = ExecuteMsg::Withdraw { project_id };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("ciuz", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), env, mock_info("bob", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "bob".to_string(),
+                amount: coins(40, "orai"),
+            })
+        );
+    }
+
+    #[test]
+    fn lifecycle() {
+        let mut deps = setup();
+        let msg = ExecuteMsg::CreateProject {
+            metadata: "example".to_string(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        let draft_id = res.attributes[1].clone().value;
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &draft_id, 50);
+        let buy = ExecuteMsg::BuyOffer {
+            project_id: draft_id.clone(),
+            offer_id,
+            metadata: "example".to_string(),
+            rate: Uint128::zero(),
+            promo_code: None,
+            referrer: None,
+        };
+        let info = mock_info("alice", &coins(50, "orai"));
+        let err = execute(deps.as_mut(), mock_env(), info, buy).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidProjectStatus {
+                status: ProjectStatus::Draft
+            }
+        ));
+
+        // one campaign misses its goal, the other reaches it
+        let deadline = Timestamp::from_seconds(mock_env().block.time.seconds() + 1000);
+        let project_id = mock_draft(deps.as_mut(), "bob");
+        let offer_id = mock_offer(deps.as_mut(), "bob", &project_id, 50);
+        for (owner, id, goal) in [("ciuz", &draft_id, 100u128), ("bob", &project_id, 50)] {
+            let msg = ExecuteMsg::UpdateGoal {
+                id: id.clone(),
+                goal: Uint128::from(goal),
+                denom: None,
+            };
+            let _res = execute(deps.as_mut(), mock_env(), mock_info(owner, &[]), msg).unwrap();
+            let msg = ExecuteMsg::UpdateTimestamp {
+                id: id.clone(),
+                timestamp: deadline.seconds(),
+            };
+            let _res = execute(deps.as_mut(), mock_env(), mock_info(owner, &[]), msg).unwrap();
+        }
+        mock_launch(deps.as_mut(), "bob", &project_id);
+
+        let launch = ExecuteMsg::LaunchProject {
+            id: draft_id.clone(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            launch.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("ciuz", &[]),
+            launch.clone(),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), launch).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidStatusTransition {
+                from: ProjectStatus::Live,
+                to: ProjectStatus::Live
+            }
+        ));
+
+        // goal and deadline are fixed once backers can pay in
+        let msg = ExecuteMsg::UpdateGoal {
+            id: draft_id.clone(),
+            goal: Uint128::from(10u128),
+            denom: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidProjectStatus {
+                status: ProjectStatus::Live
+            }
+        ));
+        let msg = ExecuteMsg::UpdateTimestamp {
+            id: draft_id.clone(),
+            timestamp: mock_env().block.time.seconds(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidProjectStatus {
+                status: ProjectStatus::Live
+            }
+        ));
+        let purchase_id = mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(50, "orai"),
+        );
+
+        let mut env = mock_env();
+        env.block.time = deadline;
+        let list = |status: ProjectStatus| QueryMsg::ListProject {
+            status: Some(status),
+        };
+        let res = query(deps.as_ref(), env.clone(), list(ProjectStatus::Failed)).unwrap();
+        let projects: Vec<ProjectResponse> = from_binary(&res).unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].project.id, draft_id);
+        let res = query(deps.as_ref(), env.clone(), list(ProjectStatus::Funded)).unwrap();
+        let projects: Vec<ProjectResponse> = from_binary(&res).unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].project.id, project_id);
+
+        // metadata is locked once funded
+        let msg = ExecuteMsg::UpdateProject {
+            id: project_id.clone(),
+            metadata: "changed".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidProjectStatus {
+                status: ProjectStatus::Funded
+            }
+        ));
+
+        let msg = ExecuteMsg::MarkShipped {
+            project_id: project_id.clone(),
+            purchase_id: purchase_id.clone(),
+            tracking_hash: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), msg).unwrap();
+        let res = query(deps.as_ref(), env.clone(), list(ProjectStatus::Delivering)).unwrap();
+        let projects: Vec<ProjectResponse> = from_binary(&res).unwrap();
+        assert_eq!(projects.len(), 1);
+        let msg = ExecuteMsg::ConfirmDelivery {
+            project_id: project_id.clone(),
+            purchase_id,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        let msg = QueryMsg::GetProject {
+            id: project_id.clone(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let project: ProjectResponse = from_binary(&res).unwrap();
+        assert_eq!(project.project.status, ProjectStatus::Completed);
+
+        let msg = ExecuteMsg::ModerateProject {
+            id: project_id,
+            action: ModerationAction::Cancel,
+            reason: "fraud".to_string(),
+        };
+        let err = execute(deps.as_mut(), env, mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidStatusTransition {
+                from: ProjectStatus::Completed,
+                to: ProjectStatus::Cancelled
+            }
+        ));
+    }
+
+    #[test]
+    fn offer_pricing() {
+        let mut deps = setup();
+        let project_id = mock_project(deps.as_mut(), "ciuz");
+        let offer_id = mock_offer(deps.as_mut(), "ciuz", &project_id, 100);
+
+        let early_bird = mock_env().block.time.plus_seconds(1000);
+        let msg = ExecuteMsg::UpdateOfferPricing {
+            id: project_id.clone(),
+            offer_id: offer_id.clone(),
+            schedule: vec![
+                PriceTier::FirstSold {
+                    count: 1,
+                    price: Uint128::from(60u128),
+                },
+                PriceTier::Until {
+                    timestamp: early_bird,
+                    price: Uint128::from(80u128),
+                },
+            ],
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+
+        let query_offer = QueryMsg::GetProjectOffer {
+            project_id: project_id.clone(),
+            offer_id: offer_id.clone(),
+        };
+        let res = query(deps.as_ref(), mock_env(), query_offer.clone()).unwrap();
+        let offer: OfferResponse = from_binary(&res).unwrap();
+        assert_eq!(offer.current_price, Uint128::from(60u128));
+
+        mock_buy(
+            deps.as_mut(),
+            "alice",
+            &project_id,
+            &offer_id,
+            &coins(60, "orai"),
+        );
+        let res = query(deps.as_ref(), mock_env(), query_offer.clone()).unwrap();
+        let offer: OfferResponse = from_binary(&res).unwrap();
+        assert_eq!(offer.sold, 1);
+        assert_eq!(offer.current_price, Uint128::from(80u128));
+
+        let mut env = mock_env();
+        env.block.time = early_bird;
+        let res = query(deps.as_ref(), env.clone(), query_offer).unwrap();
+        let offer: OfferResponse = from_binary(&res).unwrap();
+        assert_eq!(offer.current_price, Uint128::from(100u128));
+
+        let buy = ExecuteMsg::BuyOffer {
+            project_id: project_id.clone(),
+            offer_id: offer_id.clone(),
+            metadata: "example".to_string(),
+            rate: Uint128::zero(),
+            promo_code: None,
+            referrer: None,
+        };
+        let info = mock_info("bob", &coins(99, "orai"));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), buy.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFunds {}));
+
+        let msg = ExecuteMsg::BuyOffer {
+            project_id: project_id.clone(),
+            offer_id: "missing".to_string(),
+            metadata: "example".to_string(),
+            rate: Uint128::zero(),
+            promo_code: None,
+            referrer: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::OfferNotFound {}));
+
+        // expired offers can't be bought
+        let msg = ExecuteMsg::UpdateOffer {
+            id: project_id,
+            offer_id,
+            min_price: Uint128::from(100u128),
+            metadata: "example".to_string(),
+            expire_at: early_bird.seconds(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("ciuz", &[]), msg).unwrap();
+        let info = mock_info("bob", &coins(100, "orai"));
+        let err = execute(deps.as_mut(), env, info, buy).unwrap_err();
+        assert!(matches!(err, ContractError::OfferInactive {}));
     }
 
     // #[test]
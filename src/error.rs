@@ -1,9 +1,11 @@
 use std::{num::TryFromIntError, string::FromUtf8Error};
 
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use cw_utils::PaymentError;
 use thiserror::Error;
 
+use crate::state::ProjectStatus;
+
 #[derive(Error, Debug)]
 pub enum ContractError {
     #[error("{0}")]
@@ -38,6 +40,135 @@ pub enum ContractError {
 
     #[error("No data in ReceiveMsg")]
     NoData {},
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Creator is blocked")]
+    CreatorBlocked {},
+
+    #[error("Creator is not on the allow list")]
+    CreatorNotAllowed {},
+
+    #[error("Buyer is blocked")]
+    BuyerBlocked {},
+
+    #[error("User not found")]
+    UserNotFound {},
+
+    #[error("Project not found")]
+    ProjectNotFound {},
+
+    #[error("Offer not found")]
+    OfferNotFound {},
+
+    #[error("Offer is not active")]
+    OfferInactive {},
+
+    #[error("Stretch goal not found")]
+    StretchGoalNotFound {},
+
+    #[error("Invalid promo code")]
+    InvalidPromoCode {},
+
+    #[error("Promo code has expired")]
+    PromoCodeExpired {},
+
+    #[error("Promo code usage limit reached")]
+    PromoCodeExhausted {},
+
+    #[error("Share must be between 0 and 1")]
+    InvalidShare {},
+
+    #[error("Invalid referrer")]
+    InvalidReferrer {},
+
+    #[error("Project is already owned by this address")]
+    InvalidNewOwner {},
+
+    #[error("Device already registered")]
+    DeviceAlreadyExists {},
+
+    #[error("Device not found")]
+    DeviceNotFound {},
+
+    #[error("Device is retired")]
+    DeviceRetired {},
+
+    #[error("Invalid public key")]
+    InvalidPublicKey {},
+
+    #[error("Invalid signature")]
+    InvalidSignature {},
+
+    #[error("Counter must be greater than {last}")]
+    StaleCounter { last: u64 },
+
+    #[error("Milestone not found")]
+    MilestoneNotFound {},
+
+    #[error("Milestone is already settled")]
+    MilestoneSettled {},
+
+    #[error("Dispute not found")]
+    DisputeNotFound {},
+
+    #[error("Purchase already has an open dispute")]
+    DisputeAlreadyOpen {},
+
+    #[error("Dispute is already resolved")]
+    DisputeResolved {},
+
+    #[error("Purchase has not been shipped")]
+    NotShipped {},
+
+    #[error("Delivery already confirmed")]
+    AlreadyConfirmed {},
+
+    #[error("Pledge must be at least {min}")]
+    PledgeTooLow { min: Uint128 },
+
+    #[error("Purchase not found")]
+    PurchaseNotFound {},
+
+    #[error("Purchase can no longer be cancelled")]
+    CancellationClosed {},
+
+    #[error("Invalid amount")]
+    InvalidAmount {},
+
+    #[error("Fee must be between 0 and 1")]
+    InvalidFee {},
+
+    #[error("Project has no subscription plan")]
+    NoSubscriptionPlan {},
+
+    #[error("Subscription not found")]
+    SubscriptionNotFound {},
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("Project has not reached its goal")]
+    GoalNotReached {},
+
+    #[error("Project is under review")]
+    ProjectUnderReview {},
+
+    #[error("Project is cancelled")]
+    ProjectCancelled {},
+
+    #[error("Not allowed while the project is {status:?}")]
+    InvalidProjectStatus { status: ProjectStatus },
+
+    #[error("Project is funded in {denom}")]
+    InvalidFundingDenom { denom: String },
+
+    #[error("Project can't go from {from:?} to {to:?}")]
+    InvalidStatusTransition {
+        from: ProjectStatus,
+        to: ProjectStatus,
+    },
 }
 
 impl From<FromUtf8Error> for ContractError {
@@ -0,0 +1,116 @@
+use cosmwasm_std::{Addr, Event};
+
+// Every execute handler emits one `wasm-neariot` event with the attributes
+// below, so an indexer can rebuild contract state by replaying them in order.
+// Attributes that don't apply to an action are left out.
+
+// wasmd prefixes custom events with `wasm-`
+pub const EVENT_TYPE: &str = "neariot";
+
+pub const ATTR_ACTION: &str = "action";
+pub const ATTR_PROJECT_ID: &str = "project_id";
+pub const ATTR_OFFER_ID: &str = "offer_id";
+pub const ATTR_PURCHASE_ID: &str = "purchase_id";
+pub const ATTR_DEVICE_ID: &str = "device_id";
+pub const ATTR_EVIDENCE_URI: &str = "evidence_uri";
+pub const ATTR_DISPUTE_ID: &str = "dispute_id";
+pub const ATTR_MILESTONE_ID: &str = "milestone_id";
+pub const ATTR_STRETCH_GOAL_ID: &str = "stretch_goal_id";
+pub const ATTR_COLLABORATOR: &str = "collaborator";
+pub const ATTR_ACTOR: &str = "actor";
+pub const ATTR_AMOUNT: &str = "amount";
+pub const ATTR_DENOM: &str = "denom";
+pub const ATTR_OLD_VALUE: &str = "old_value";
+pub const ATTR_NEW_VALUE: &str = "new_value";
+
+// User
+pub const ACTION_REGISTER_USER: &str = "register_user";
+
+// Project
+pub const ACTION_CREATE_PROJECT: &str = "create_project";
+pub const ACTION_UPDATE_PROJECT: &str = "update_project";
+pub const ACTION_LAUNCH_PROJECT: &str = "launch_project";
+pub const ACTION_UPDATE_TIMESTAMP: &str = "update_timestamp";
+pub const ACTION_UPDATE_GOAL: &str = "update_goal";
+pub const ACTION_CREATE_OFFER: &str = "create_offer";
+pub const ACTION_UPDATE_OFFER: &str = "update_offer";
+pub const ACTION_UPDATE_OFFER_PRICING: &str = "update_offer_pricing";
+pub const ACTION_DELETE_OFFER: &str = "delete_offer";
+pub const ACTION_SET_COLLABORATOR: &str = "set_collaborator";
+pub const ACTION_PROPOSE_OWNERSHIP_TRANSFER: &str = "propose_ownership_transfer";
+pub const ACTION_ACCEPT_OWNERSHIP_TRANSFER: &str = "accept_ownership_transfer";
+pub const ACTION_BUY_OFFER: &str = "buy_offer";
+pub const ACTION_RATE_OFFER: &str = "rate_offer";
+pub const ACTION_ADD_PROMO_CODE: &str = "add_promo_code";
+pub const ACTION_REMOVE_PROMO_CODE: &str = "remove_promo_code";
+pub const ACTION_ADD_STRETCH_GOAL: &str = "add_stretch_goal";
+pub const ACTION_REMOVE_STRETCH_GOAL: &str = "remove_stretch_goal";
+pub const ACTION_STRETCH_GOAL_REACHED: &str = "stretch_goal_reached";
+pub const ACTION_UPDATE_MIN_PLEDGE: &str = "update_min_pledge";
+pub const ACTION_PLEDGE: &str = "pledge";
+pub const ACTION_UPDATE_CANCELLATION_POLICY: &str = "update_cancellation_policy";
+pub const ACTION_CANCEL_PURCHASE: &str = "cancel_purchase";
+pub const ACTION_MARK_SHIPPED: &str = "mark_shipped";
+pub const ACTION_CONFIRM_DELIVERY: &str = "confirm_delivery";
+pub const ACTION_ADD_MILESTONE: &str = "add_milestone";
+pub const ACTION_COMPLETE_MILESTONE: &str = "complete_milestone";
+pub const ACTION_FAIL_MILESTONE: &str = "fail_milestone";
+pub const ACTION_ATTEST_MILESTONE: &str = "attest_milestone";
+
+// Subscription
+pub const ACTION_UPDATE_SUBSCRIPTION_PLAN: &str = "update_subscription_plan";
+pub const ACTION_SUBSCRIBE: &str = "subscribe";
+pub const ACTION_CLAIM_SUBSCRIPTION: &str = "claim_subscription";
+pub const ACTION_CANCEL_SUBSCRIPTION: &str = "cancel_subscription";
+
+// Watching
+pub const ACTION_WATCH_PROJECT: &str = "watch_project";
+pub const ACTION_UNWATCH_PROJECT: &str = "unwatch_project";
+
+// Rating
+pub const ACTION_RATE_PROJECT: &str = "rate_project";
+
+// Refund
+pub const ACTION_CREDIT_REFUND: &str = "credit_refund";
+pub const ACTION_CLAIM_REFUND: &str = "claim_refund";
+
+// Withdrawal
+pub const ACTION_WITHDRAW: &str = "withdraw";
+
+// Referral
+pub const ACTION_UPDATE_REFERRAL_SHARE: &str = "update_referral_share";
+pub const ACTION_CREDIT_REFERRAL_REWARD: &str = "credit_referral_reward";
+pub const ACTION_CLAIM_REFERRAL_REWARDS: &str = "claim_referral_rewards";
+
+// Device
+pub const ACTION_REGISTER_DEVICE: &str = "register_device";
+pub const ACTION_ROTATE_DEVICE_KEY: &str = "rotate_device_key";
+pub const ACTION_RETIRE_DEVICE: &str = "retire_device";
+pub const ACTION_SUBMIT_TELEMETRY: &str = "submit_telemetry";
+
+// Dispute
+pub const ACTION_OPEN_DISPUTE: &str = "open_dispute";
+pub const ACTION_RESOLVE_DISPUTE: &str = "resolve_dispute";
+
+// Admin
+pub const ACTION_PAUSE: &str = "pause";
+pub const ACTION_UNPAUSE: &str = "unpause";
+pub const ACTION_MODERATE_PROJECT: &str = "moderate_project";
+pub const ACTION_UPDATE_ACCESS_LIST: &str = "update_access_list";
+pub const ACTION_SET_VERIFIED_CREATORS_ONLY: &str = "set_verified_creators_only";
+pub const ACTION_ADD_VERIFIER: &str = "add_verifier";
+pub const ACTION_REMOVE_VERIFIER: &str = "remove_verifier";
+pub const ACTION_ADD_ORACLE: &str = "add_oracle";
+pub const ACTION_REMOVE_ORACLE: &str = "remove_oracle";
+pub const ACTION_ADD_ARBITRATOR: &str = "add_arbitrator";
+pub const ACTION_REMOVE_ARBITRATOR: &str = "remove_arbitrator";
+
+// Verification
+pub const ACTION_GRANT_VERIFICATION: &str = "grant_verification";
+pub const ACTION_REVOKE_VERIFICATION: &str = "revoke_verification";
+
+pub fn neariot_event(action: &str, actor: &Addr) -> Event {
+    Event::new(EVENT_TYPE)
+        .add_attribute(ATTR_ACTION, action)
+        .add_attribute(ATTR_ACTOR, actor)
+}
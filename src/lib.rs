@@ -3,6 +3,8 @@ pub mod contract;
 #[allow(unused_imports)]
 mod error;
 #[allow(unused_imports)]
+pub mod events;
+#[allow(unused_imports)]
 pub mod helpers;
 #[allow(unused_imports)]
 pub mod msg;
@@ -1,8 +1,12 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Uint128};
-use cw20::Denom;
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, Timestamp, Uint128};
 
-use crate::state::{Offer, Project, User};
+use crate::state::{
+    AccessConfig, AccessList, Attestation, Contribution, Device, Discount, Dispute,
+    DisputeResolution, DisputeStatus, KeyType, Milestone, MilestoneCondition, ModerationAction,
+    ModerationRecord, Offer, PauseConfig, PauseOperation, PriceTier, Project, ProjectStatus,
+    PromoCode, Reputation, Role, StretchGoal, Subscription, User,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {}
@@ -19,11 +23,35 @@ pub enum ExecuteMsg {
         id: String,
         metadata: String,
     },
+    // owner only, opens a draft project to backers
+    LaunchProject {
+        id: String,
+    },
     UpdateTimestamp {
         id: String,
         timestamp: u64,
     },
+    // `denom` fixes the one denom the project is funded in
+    UpdateGoal {
+        id: String,
+        goal: Uint128,
+        denom: Option<String>,
+    },
     DeleteProject {},
+    // owner only, an empty `roles` removes the collaborator
+    SetCollaborator {
+        id: String,
+        address: String,
+        roles: Vec<Role>,
+    },
+    // owner only, `None` withdraws a pending proposal
+    ProposeOwnershipTransfer {
+        id: String,
+        new_owner: Option<String>,
+    },
+    AcceptOwnershipTransfer {
+        id: String,
+    },
     CreateOffer {
         id: String,
         min_price: Uint128,
@@ -37,6 +65,12 @@ pub enum ExecuteMsg {
         metadata: String,
         expire_at: u64,
     },
+    // replaces the offer's price schedule, an empty list restores `min_price`
+    UpdateOfferPricing {
+        id: String,
+        offer_id: String,
+        schedule: Vec<PriceTier>,
+    },
     DeleteOffer {
         id: String,
         offer_id: String,
@@ -46,12 +80,100 @@ pub enum ExecuteMsg {
         offer_id: String,
         metadata: String,
         rate: Uint128,
+        // plaintext code, checked against the registered hashes
+        promo_code: Option<String>,
+        referrer: Option<String>,
     },
     RateOffer {
         project_id: String,
         offer_id: String,
         rate: Uint128,
     },
+    AddPromoCode {
+        id: String,
+        // sha256 of the plaintext code
+        code_hash: Binary,
+        discount: Discount,
+        max_uses: u32,
+        expire_at: Option<u64>,
+    },
+    RemovePromoCode {
+        id: String,
+        code_hash: Binary,
+    },
+    // tranches of all milestones may add up to at most 1
+    AddMilestone {
+        id: String,
+        metadata: String,
+        tranche: Decimal,
+        condition: MilestoneCondition,
+    },
+    // owner only, can be repeated to update the tracking reference
+    MarkShipped {
+        project_id: String,
+        purchase_id: String,
+        tracking_hash: Option<Binary>,
+    },
+    // buyer only
+    ConfirmDelivery {
+        project_id: String,
+        purchase_id: String,
+    },
+    // oracle vote on an `Oracle` milestone, replacing the oracle's earlier vote
+    AttestMilestone {
+        project_id: String,
+        milestone_id: String,
+        met: bool,
+        evidence_uri: String,
+    },
+    // linked offers can't be bought until `total_pledged` reaches `threshold`
+    AddStretchGoal {
+        id: String,
+        threshold: Uint128,
+        offer_ids: Vec<String>,
+        metadata: String,
+    },
+    RemoveStretchGoal {
+        id: String,
+        goal_id: String,
+    },
+    UpdateMinPledge {
+        id: String,
+        min_pledge: Uint128,
+    },
+    Pledge {
+        project_id: String,
+    },
+    UpdateCancellationPolicy {
+        id: String,
+        cutoff: Option<u64>,
+        fee: Decimal,
+    },
+    UpdateSubscriptionPlan {
+        id: String,
+        period: u64,
+        min_price: Uint128,
+    },
+    // funds are split evenly over `periods`
+    Subscribe {
+        project_id: String,
+        periods: u32,
+    },
+    ClaimSubscription {
+        project_id: String,
+        subscription_id: String,
+    },
+    CancelSubscription {
+        project_id: String,
+        subscription_id: String,
+    },
+    // `purchase_id` is a bought offer or a pledge, `amount` reduces it instead
+    // of cancelling it outright
+    CancelPurchase {
+        project_id: String,
+        purchase_id: String,
+        amount: Option<Uint128>,
+    },
 
     // Watching
     WatchProject {
@@ -66,6 +188,113 @@ pub enum ExecuteMsg {
         id: String,
         rate: Uint128,
     },
+
+    // Refund
+    ClaimRefund {},
+
+    // Withdrawal
+    Withdraw {
+        project_id: String,
+    },
+
+    // Referral
+    UpdateReferralShare {
+        id: String,
+        share: Decimal,
+    },
+    ClaimReferralRewards {},
+
+    // Device
+    RegisterDevice {
+        project_id: String,
+        device_id: String,
+        pubkey: Binary,
+        key_type: KeyType,
+        model: String,
+    },
+    RotateDeviceKey {
+        project_id: String,
+        device_id: String,
+        pubkey: Binary,
+        key_type: KeyType,
+    },
+    RetireDevice {
+        project_id: String,
+        device_id: String,
+    },
+    // anyone may relay, the device key signs `utils::telemetry_digest`
+    SubmitTelemetry {
+        project_id: String,
+        device_id: String,
+        payload_hash: Binary,
+        counter: u64,
+        signature: Binary,
+    },
+
+    // Dispute
+    OpenDispute {
+        project_id: String,
+        purchase_id: String,
+        reason: String,
+        evidence_uri: String,
+    },
+    // admin or arbitrator only
+    ResolveDispute {
+        dispute_id: String,
+        resolution: DisputeResolution,
+    },
+
+    // Admin
+    // `None` pauses every state-changing operation
+    Pause {
+        operation: Option<PauseOperation>,
+    },
+    Unpause {
+        operation: Option<PauseOperation>,
+    },
+    ModerateProject {
+        id: String,
+        action: ModerationAction,
+        reason: String,
+    },
+    UpdateAccessList {
+        list: AccessList,
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    SetVerifiedCreatorsOnly {
+        enabled: bool,
+    },
+    AddVerifier {
+        address: String,
+    },
+    RemoveVerifier {
+        address: String,
+    },
+    AddArbitrator {
+        address: String,
+    },
+    RemoveArbitrator {
+        address: String,
+    },
+    // `None` trusts the oracle for every project
+    AddOracle {
+        address: String,
+        project_id: Option<String>,
+    },
+    RemoveOracle {
+        address: String,
+        project_id: Option<String>,
+    },
+
+    // Verification
+    GrantVerification {
+        address: String,
+        expire_at: Option<u64>,
+    },
+    RevokeVerification {
+        address: String,
+    },
 }
 
 #[cw_serde]
@@ -78,25 +307,79 @@ pub enum QueryMsg {
     ListUser {},
 
     // Project
-    #[returns(Project)]
+    #[returns(ProjectResponse)]
     GetProject { id: String },
-    #[returns(Vec<Project>)]
-    ListProject {},
+    #[returns(Vec<ProjectResponse>)]
+    ListProject { status: Option<ProjectStatus> },
 
     // Project Offers
-    #[returns(Vec<Offer>)]
+    #[returns(Vec<OfferResponse>)]
     GetProjectOffers { project_id: String },
-    #[returns(Offer)]
+    #[returns(OfferResponse)]
     GetProjectOffer {
         project_id: String,
         offer_id: String,
     },
 
+    #[returns(Vec<PromoCode>)]
+    ListPromoCodes { project_id: String },
+    #[returns(Vec<Milestone>)]
+    GetMilestones { project_id: String },
+    #[returns(Vec<StretchGoal>)]
+    GetStretchGoals { project_id: String },
+
+    #[returns(ReputationResponse)]
+    GetReputation { owner: String },
+
     // Funding
-    #[returns(String)]
-    GetFunding {},
-    #[returns(String)]
-    ListFunding {},
+    #[returns(FundingResponse)]
+    GetFunding { project_id: String },
+    #[returns(Vec<FundingResponse>)]
+    ListFunding {
+        status: Option<FundingStatus>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Contributions
+    #[returns(Vec<Contribution>)]
+    ListProjectContributions {
+        project_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(Vec<Contribution>)]
+    ListBackerContributions {
+        backer: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Subscriptions
+    #[returns(Vec<Subscription>)]
+    ListSubscriptions {
+        project_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Device
+    #[returns(Device)]
+    GetDevice {
+        project_id: String,
+        device_id: String,
+    },
+    #[returns(Vec<Device>)]
+    ListDevices {
+        project_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(Option<Attestation>)]
+    GetAttestation {
+        project_id: String,
+        device_id: String,
+    },
 
     // Watching +
     #[returns(Project)]
@@ -109,10 +392,133 @@ pub enum QueryMsg {
     ListRating {},
 
     // Balance
-    #[returns(Denom)]
+    #[returns(BalanceResponse)]
     GetBalance {},
+    #[returns(ProjectBalanceResponse)]
+    GetProjectBalance { project_id: String },
+    #[returns(UserBalanceResponse)]
+    GetUserBalance { address: String },
+
+    // Referral
+    #[returns(ReferralStatsResponse)]
+    GetReferralStats { address: String },
+
+    // Dispute
+    #[returns(Dispute)]
+    GetDispute { dispute_id: String },
+    #[returns(Vec<Dispute>)]
+    ListDisputes {
+        project_id: Option<String>,
+        status: Option<DisputeStatus>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 
     // Admin +
     #[returns(Addr)]
     GetAdmin {},
+    #[returns(PauseConfig)]
+    GetPause {},
+    #[returns(AccessConfig)]
+    GetAccess {},
+    #[returns(Vec<Addr>)]
+    ListVerifiers {},
+    // global oracles when `project_id` is `None`
+    #[returns(Vec<Addr>)]
+    ListOracles { project_id: Option<String> },
+    #[returns(Vec<Addr>)]
+    ListArbitrators {},
+    #[returns(Vec<ModerationRecord>)]
+    ListModeration { project_id: Option<String> },
+}
+
+#[cw_serde]
+pub struct ProjectResponse {
+    pub project: Project,
+    pub owner_verified: bool,
+    pub fulfillment: FulfillmentStats,
+}
+
+#[cw_serde]
+pub struct ReputationResponse {
+    pub owner: Addr,
+    pub reputation: Reputation,
+    pub avg_rating: Uint128,
+    // mean of the completion, non-refund, non-upheld-dispute and confirmation
+    // ratios that have any data, `None` for owners without history
+    pub score: Option<Decimal>,
+}
+
+#[cw_serde]
+pub struct FulfillmentStats {
+    pub purchases: u32,
+    pub shipped: u32,
+    // confirmed by the buyer or auto-confirmed
+    pub delivered: u32,
+    // delivered / purchases
+    pub fulfillment_rate: Decimal,
+}
+
+#[cw_serde]
+pub struct OfferResponse {
+    pub offer: Offer,
+    pub sold: u32,
+    pub current_price: Uint128,
+}
+
+#[cw_serde]
+pub enum FundingStatus {
+    Active,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[cw_serde]
+pub struct FundingResponse {
+    pub project_id: String,
+    pub goal: Uint128,
+    pub goal_denom: Option<String>,
+    pub total_pledged: Uint128,
+    pub raised: Vec<Coin>,
+    pub backers: u64,
+    pub percent_funded: u64,
+    pub deadline: Timestamp,
+    pub status: FundingStatus,
+}
+
+#[cw_serde]
+pub struct BalanceResponse {
+    // funds held for projects plus unclaimed refunds
+    pub escrow: Vec<Coin>,
+    // funds actually held by the contract
+    pub actual: Vec<Coin>,
+    pub surplus: Vec<Coin>,
+    pub shortfall: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct ProjectBalanceResponse {
+    pub project_id: String,
+    pub escrowed: Vec<Coin>,
+    pub withdrawable: Vec<Coin>,
+    pub withdrawn: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct UserBalanceResponse {
+    pub address: Addr,
+    pub claimable_refunds: Vec<Coin>,
+    pub claimable_rewards: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct ReferralStatsResponse {
+    pub address: Addr,
+    pub referrals: u32,
+    pub volume: Vec<Coin>,
+    // rewards of referred purchases not yet settled
+    pub pending: Vec<Coin>,
+    pub earned: Vec<Coin>,
+    pub claimable: Vec<Coin>,
 }
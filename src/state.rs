@@ -1,6 +1,6 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Timestamp, Uint128};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, Empty, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map};
 
 #[cw_serde]
 pub struct Offer {
@@ -8,16 +8,105 @@ pub struct Offer {
     pub min_price: Uint128,
     pub metadata: String,
     pub create_at: Timestamp,
+    // zero means the offer doesn't expire
     pub expire_at: Timestamp,
+    // offers linked to a stretch goal stay inactive until it is reached
+    pub active: bool,
+    // the first matching tier sets the price, `min_price` applies otherwise
+    pub price_schedule: Vec<PriceTier>,
 }
+
+impl Offer {
+    pub fn current_price(&self, now: Timestamp, sold: u32) -> Uint128 {
+        self.price_schedule
+            .iter()
+            .find_map(|tier| match tier {
+                PriceTier::Until { timestamp, price } => (now < *timestamp).then(|| *price),
+                PriceTier::FirstSold { count, price } => (sold < *count).then(|| *price),
+            })
+            .unwrap_or(self.min_price)
+    }
+
+    pub fn is_expired(&self, now: Timestamp) -> bool {
+        self.expire_at.nanos() != 0 && now >= self.expire_at
+    }
+}
+
+#[cw_serde]
+pub enum PriceTier {
+    // applies to purchases made before `timestamp`
+    Until {
+        timestamp: Timestamp,
+        price: Uint128,
+    },
+    // applies to the first `count` purchases of the offer
+    FirstSold {
+        count: u32,
+        price: Uint128,
+    },
+}
+
 #[cw_serde]
 pub struct BougthOffer {
     pub id: String,
+    pub offer_id: String,
+    // what the buyer paid, less anything cancelled or refunded since
     pub price: Uint128,
+    // the offer's price at purchase, after tiers and promo codes
+    pub offer_price: Uint128,
+    pub denom: String,
     pub create_at: Timestamp,
     pub metadata: String,
     pub rate: Uint128,
     pub buyer: Addr,
+    // metadata of stretch goals unlocked for this purchase
+    pub extras: Vec<String>,
+    // hash of the promo code redeemed for this purchase
+    pub promo_code: Option<Binary>,
+    pub referral: Option<Referral>,
+    pub shipment: Option<Shipment>,
+}
+
+#[cw_serde]
+pub struct Shipment {
+    pub shipped_at: Timestamp,
+    // hash of the carrier's tracking reference
+    pub tracking_hash: Option<Binary>,
+    pub confirmed_at: Option<Timestamp>,
+}
+
+impl Shipment {
+    /// Unconfirmed shipments count as delivered once `auto_confirm` seconds pass.
+    pub fn is_delivered(&self, now: Timestamp, auto_confirm: u64) -> bool {
+        self.confirmed_at.is_some() || now >= self.shipped_at.plus_seconds(auto_confirm)
+    }
+}
+
+#[cw_serde]
+pub struct Referral {
+    pub referrer: Addr,
+    // project's referral share when the purchase was made
+    pub share: Decimal,
+    // set once the reward is credited on withdrawal
+    pub settled: bool,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct ReferralStats {
+    pub referrals: u32,
+    // purchases brought in, net of cancellations
+    pub volume: Vec<Coin>,
+    // rewards credited so far, claimed or not
+    pub earned: Vec<Coin>,
+}
+#[cw_serde]
+pub struct Pledge {
+    pub id: String,
+    pub amount: Uint128,
+    pub denom: String,
+    pub backer: Addr,
+    pub create_at: Timestamp,
 }
 #[cw_serde]
 pub struct User {
@@ -27,6 +116,156 @@ pub struct User {
     pub project_funded: Vec<String>,
     pub project_watched: Vec<String>,
     pub project_owned: Vec<String>,
+    pub verification: Option<Verification>,
+}
+
+#[cw_serde]
+pub struct Verification {
+    pub verifier: Addr,
+    pub granted_at: Timestamp,
+    pub expire_at: Option<Timestamp>,
+}
+
+impl User {
+    pub fn is_verified(&self, now: Timestamp) -> bool {
+        match &self.verification {
+            Some(verification) => verification
+                .expire_at
+                .map_or(true, |expire_at| now < expire_at),
+            None => false,
+        }
+    }
+}
+
+// per owner counters, updated as the events happen
+#[cw_serde]
+#[derive(Default)]
+pub struct Reputation {
+    // launched campaigns
+    pub campaigns: u32,
+    // campaigns that reached Completed, only counted by `GetReputation` since
+    // nothing settles that transition on chain
+    pub completed_campaigns: u32,
+    // campaigns cancelled by moderation, `GetReputation` adds those that
+    // missed their goal for the same reason
+    pub failed_campaigns: u32,
+    // purchases and pledges
+    pub backings: u32,
+    // backings cancelled or refunded through a dispute
+    pub refunds: u32,
+    pub disputes: u32,
+    // resolved with a full or partial refund
+    pub disputes_upheld: u32,
+    pub disputes_rejected: u32,
+    pub shipments: u32,
+    // auto-confirmed deliveries aren't counted, they leave no on-chain trace
+    pub confirmed_deliveries: u32,
+    pub rating_sum: Uint128,
+    pub rating_count: u32,
+}
+
+impl Reputation {
+    pub fn add(&mut self, other: &Reputation) {
+        self.campaigns += other.campaigns;
+        self.completed_campaigns += other.completed_campaigns;
+        self.failed_campaigns += other.failed_campaigns;
+        self.backings += other.backings;
+        self.refunds += other.refunds;
+        self.disputes += other.disputes;
+        self.disputes_upheld += other.disputes_upheld;
+        self.disputes_rejected += other.disputes_rejected;
+        self.shipments += other.shipments;
+        self.confirmed_deliveries += other.confirmed_deliveries;
+        self.rating_sum += other.rating_sum;
+        self.rating_count += other.rating_count;
+    }
+
+    pub fn remove(&mut self, other: &Reputation) {
+        self.campaigns = self.campaigns.saturating_sub(other.campaigns);
+        self.completed_campaigns = self
+            .completed_campaigns
+            .saturating_sub(other.completed_campaigns);
+        self.failed_campaigns = self.failed_campaigns.saturating_sub(other.failed_campaigns);
+        self.backings = self.backings.saturating_sub(other.backings);
+        self.refunds = self.refunds.saturating_sub(other.refunds);
+        self.disputes = self.disputes.saturating_sub(other.disputes);
+        self.disputes_upheld = self.disputes_upheld.saturating_sub(other.disputes_upheld);
+        self.disputes_rejected = self
+            .disputes_rejected
+            .saturating_sub(other.disputes_rejected);
+        self.shipments = self.shipments.saturating_sub(other.shipments);
+        self.confirmed_deliveries = self
+            .confirmed_deliveries
+            .saturating_sub(other.confirmed_deliveries);
+        self.rating_sum = self.rating_sum.saturating_sub(other.rating_sum);
+        self.rating_count = self.rating_count.saturating_sub(other.rating_count);
+    }
+}
+
+#[cw_serde]
+pub enum Role {
+    // project metadata, offers and campaign settings
+    Editor,
+    // withdrawals, paid out to the owner
+    Treasurer,
+    // shipments and telemetry devices
+    Reporter,
+}
+
+impl Role {
+    // matches the snake_case names roles are serialized with
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Editor => "editor",
+            Role::Treasurer => "treasurer",
+            Role::Reporter => "reporter",
+        }
+    }
+}
+
+#[cw_serde]
+pub enum ProjectStatus {
+    // being set up by the owner, can't be backed yet
+    Draft,
+    // accepting purchases and pledges until the deadline
+    Live,
+    // reached its goal by the deadline
+    Funded,
+    // missed its goal by the deadline
+    Failed,
+    // the owner started shipping
+    Delivering,
+    // every purchase delivered and every milestone completed
+    Completed,
+    // cancelled by moderation, backers shared what was left in escrow
+    Cancelled,
+}
+
+impl ProjectStatus {
+    pub fn can_transition_to(&self, next: &ProjectStatus) -> bool {
+        matches!(
+            (self, next),
+            (ProjectStatus::Draft, ProjectStatus::Live)
+                | (ProjectStatus::Live, ProjectStatus::Funded)
+                | (ProjectStatus::Live, ProjectStatus::Failed)
+                | (ProjectStatus::Funded, ProjectStatus::Delivering)
+                | (ProjectStatus::Delivering, ProjectStatus::Completed)
+                | (
+                    ProjectStatus::Draft
+                        | ProjectStatus::Live
+                        | ProjectStatus::Funded
+                        | ProjectStatus::Failed
+                        | ProjectStatus::Delivering,
+                    ProjectStatus::Cancelled
+                )
+        )
+    }
+}
+
+#[cw_serde]
+pub struct Collaborator {
+    pub address: Addr,
+    pub roles: Vec<Role>,
 }
 
 #[cw_serde]
@@ -36,13 +275,361 @@ pub struct Project {
     pub metadata: String,
     pub avg_rate: Uint128,
     pub create_at: Timestamp,
+    // pledged in `goal_denom`, the only denom the project accepts
     pub total_pledged: Uint128,
+    pub goal: Uint128,
+    // set with the goal, or by the first payment when the goal names none
+    pub goal_denom: Option<String>,
+    pub withdrawn: Vec<Coin>,
     pub watchers: Vec<Addr>,
     pub offers: Vec<Offer>,
     pub bougth_offers: Vec<BougthOffer>,
+    // pledges without an offer must be at least `min_pledge`
+    pub min_pledge: Uint128,
+    pub pledges: Vec<Pledge>,
+    pub cancellation: CancellationPolicy,
+    // fees kept from cancelled purchases and pledges
+    pub cancel_fees: Vec<Coin>,
+    pub subscription_plan: Option<SubscriptionPlan>,
+    // share of referred purchases paid to the referrer out of the owner's proceeds
+    pub referral_share: Decimal,
+    // funds assigned to a milestone tranche stay locked until it completes
+    pub milestones: Vec<Milestone>,
+    // escrow held back by open disputes
+    pub frozen: Vec<Coin>,
+    pub collaborators: Vec<Collaborator>,
+    // set by the owner, becomes the owner once it accepts
+    pub pending_owner: Option<Addr>,
+    pub stretch_goals: Vec<StretchGoal>,
     pub milestone: Timestamp,
+    pub hidden: bool,
+    pub under_review: bool,
+    pub status: ProjectStatus,
+}
+
+impl Project {
+    /// The owner holds every role.
+    pub fn has_role(&self, address: &Addr, role: Role) -> bool {
+        &self.owner == address
+            || self.collaborators.iter().any(|collaborator| {
+                &collaborator.address == address && collaborator.roles.contains(&role)
+            })
+    }
+}
+
+#[cw_serde]
+pub struct Contribution {
+    pub project_id: String,
+    pub backer: Addr,
+    pub amount: Vec<Coin>,
+    pub purchase_count: u32,
+    pub first_at: Timestamp,
+    pub last_at: Timestamp,
+}
+
+impl Contribution {
+    // what the backer has in `denom`, used to rank backers in the goal's denom
+    pub fn amount_of(&self, denom: &str) -> Uint128 {
+        self.amount
+            .iter()
+            .find(|coin| coin.denom == denom)
+            .map_or(Uint128::zero(), |coin| coin.amount)
+    }
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct CancellationPolicy {
+    // backers can't cancel after this time, even before the deadline
+    pub cutoff: Option<Timestamp>,
+    // share of the cancelled amount kept by the project
+    pub fee: Decimal,
+}
+
+#[cw_serde]
+pub enum KeyType {
+    Secp256k1,
+    Ed25519,
+}
+
+impl KeyType {
+    pub fn is_valid_key(&self, pubkey: &[u8]) -> bool {
+        match self {
+            // compressed or uncompressed SEC1 encoding
+            KeyType::Secp256k1 => pubkey.len() == 33 || pubkey.len() == 65,
+            KeyType::Ed25519 => pubkey.len() == 32,
+        }
+    }
+}
+
+#[cw_serde]
+pub enum DeviceStatus {
+    Active,
+    Retired,
+}
+
+#[cw_serde]
+pub struct Device {
+    pub id: String,
+    pub project_id: String,
+    pub pubkey: Binary,
+    pub key_type: KeyType,
+    pub model: String,
+    pub status: DeviceStatus,
+    pub registered_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+#[cw_serde]
+pub enum DisputeStatus {
+    Open,
+    Refunded,
+    PartiallyRefunded,
+    Rejected,
+    // the project was cancelled and the purchase refunded with the rest of escrow
+    Closed,
+}
+
+#[cw_serde]
+pub enum DisputeResolution {
+    // refund the whole frozen amount
+    Refund,
+    PartialRefund { amount: Uint128 },
+    Reject,
+}
+
+#[cw_serde]
+pub struct Dispute {
+    pub id: String,
+    pub project_id: String,
+    pub purchase_id: String,
+    pub backer: Addr,
+    pub reason: String,
+    pub evidence_uri: String,
+    // the purchase's share of unreleased escrow when the dispute was opened
+    pub frozen: Coin,
+    pub status: DisputeStatus,
+    pub refunded: Uint128,
+    pub resolver: Option<Addr>,
+    pub create_at: Timestamp,
+    pub resolved_at: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub enum MilestoneCondition {
+    // at least `min_devices` active devices attested at or after `after`
+    Telemetry { min_devices: u32, after: Timestamp },
+    // `quorum` oracles agree the milestone was met, or failed
+    Oracle { quorum: u32 },
+}
+
+#[cw_serde]
+pub struct OracleVote {
+    pub oracle: Addr,
+    pub met: bool,
+    pub evidence_uri: String,
+    pub create_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct Milestone {
+    pub id: String,
+    pub metadata: String,
+    // share of raised funds released on completion
+    pub tranche: Decimal,
+    pub condition: MilestoneCondition,
+    pub completed_at: Option<Timestamp>,
+    // a failed milestone keeps its tranche locked
+    pub failed_at: Option<Timestamp>,
+    // latest vote of each oracle
+    pub votes: Vec<OracleVote>,
+}
+
+impl Milestone {
+    pub fn is_open(&self) -> bool {
+        self.completed_at.is_none() && self.failed_at.is_none()
+    }
+}
+
+// latest signed telemetry accepted for a device
+#[cw_serde]
+pub struct Attestation {
+    pub project_id: String,
+    pub device_id: String,
+    pub payload_hash: Binary,
+    pub counter: u64,
+    pub signature: Binary,
+    pub submitter: Addr,
+    pub submitted_at: Timestamp,
+}
+
+#[cw_serde]
+pub enum Discount {
+    // share of the price taken off, between 0 and 1
+    Percent(Decimal),
+    Fixed(Uint128),
+}
+
+impl Discount {
+    pub fn apply(&self, price: Uint128) -> Uint128 {
+        match self {
+            Discount::Percent(percent) => price - price * *percent,
+            Discount::Fixed(amount) => price.saturating_sub(*amount),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct PromoCode {
+    pub hash: Binary,
+    pub discount: Discount,
+    pub max_uses: u32,
+    pub used: u32,
+    pub expire_at: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct StretchGoal {
+    pub id: String,
+    pub threshold: Uint128,
+    pub offer_ids: Vec<String>,
+    pub metadata: String,
+    pub reached_at: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct SubscriptionPlan {
+    // period length in seconds
+    pub period: u64,
+    pub min_price: Uint128,
+}
+
+#[cw_serde]
+pub struct Subscription {
+    pub id: String,
+    pub project_id: String,
+    pub backer: Addr,
+    pub amount_per_period: Uint128,
+    pub denom: String,
+    pub period: u64,
+    // periods paid for, shrinks to the periods already due on cancel
+    pub periods: u32,
+    pub claimed: u32,
+    pub start_at: Timestamp,
+    pub cancelled: bool,
+}
+
+impl Subscription {
+    // periods whose end has passed, the owner may claim these
+    pub fn due_periods(&self, now: Timestamp) -> u32 {
+        let elapsed = now.seconds().saturating_sub(self.start_at.seconds()) / self.period;
+        elapsed.min(self.periods as u64) as u32
+    }
+}
+
+#[cw_serde]
+pub enum ModerationAction {
+    Hide,
+    Unhide,
+    Flag,
+    Unflag,
+    Cancel,
+}
+
+impl ModerationAction {
+    // matches the snake_case names actions are serialized with
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationAction::Hide => "hide",
+            ModerationAction::Unhide => "unhide",
+            ModerationAction::Flag => "flag",
+            ModerationAction::Unflag => "unflag",
+            ModerationAction::Cancel => "cancel",
+        }
+    }
+}
+
+#[cw_serde]
+pub struct ModerationRecord {
+    pub project_id: String,
+    pub action: ModerationAction,
+    pub reason: String,
+    pub actor: Addr,
+    pub create_at: Timestamp,
+}
+
+#[cw_serde]
+pub enum PauseOperation {
+    Purchases,
+    ProjectCreation,
+    Withdrawals,
+}
+
+impl PauseOperation {
+    // matches the snake_case names operations are serialized with
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PauseOperation::Purchases => "purchases",
+            PauseOperation::ProjectCreation => "project_creation",
+            PauseOperation::Withdrawals => "withdrawals",
+        }
+    }
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct PauseConfig {
+    pub global: bool,
+    pub operations: Vec<PauseOperation>,
+}
+
+#[cw_serde]
+pub enum AccessList {
+    BlockedCreators,
+    BlockedBuyers,
+    AllowedCreators,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct AccessConfig {
+    pub blocked_creators: Vec<Addr>,
+    pub blocked_buyers: Vec<Addr>,
+    pub allowed_creators: Vec<Addr>,
+    // when set, only `allowed_creators` may create projects
+    pub verified_creators_only: bool,
 }
 
 pub const ADMIN: Item<Addr> = Item::new("admin");
+pub const ACCESS: Item<AccessConfig> = Item::new("access");
+pub const VERIFIERS: Item<Vec<Addr>> = Item::new("verifiers");
+// oracles trusted for every project
+pub const GLOBAL_ORACLES: Item<Vec<Addr>> = Item::new("global_oracles");
+pub const PROJECT_ORACLES: Map<&str, Vec<Addr>> = Map::new("project_oracles");
+pub const ARBITRATORS: Item<Vec<Addr>> = Item::new("arbitrators");
+pub const DISPUTES: Map<&str, Dispute> = Map::new("disputes");
+// (project_id, purchase_id) -> id of the purchase's open dispute
+pub const OPEN_DISPUTES: Map<(&str, &str), String> = Map::new("open_disputes");
+// (project_id, dispute_id) -> purchase_id, every dispute opened on the project
+pub const PROJECT_DISPUTES: Map<(&str, &str), String> = Map::new("project_disputes");
+// last id handed out to a purchase, pledge, subscription or dispute
+pub const ID_COUNTER: Item<u64> = Item::new("id_counter");
+pub const PAUSE: Item<PauseConfig> = Item::new("pause");
 pub const USERS: Item<Vec<User>> = Item::new("users");
 pub const PROJECTS: Item<Vec<Project>> = Item::new("projects");
+pub const MODERATION_LOG: Item<Vec<ModerationRecord>> = Item::new("moderation_log");
+pub const CONTRIBUTIONS: Map<(&str, &Addr), Contribution> = Map::new("contributions");
+// (backer, project_id) index over `CONTRIBUTIONS`
+pub const BACKED_PROJECTS: Map<(&Addr, &str), Empty> = Map::new("backed_projects");
+pub const SUBSCRIPTIONS: Map<(&str, &str), Subscription> = Map::new("subscriptions");
+// keyed by (project_id, sha256 of the code)
+pub const PROMO_CODES: Map<(&str, &[u8]), PromoCode> = Map::new("promo_codes");
+// keyed by (project_id, device_id)
+pub const DEVICES: Map<(&str, &str), Device> = Map::new("devices");
+pub const ATTESTATIONS: Map<(&str, &str), Attestation> = Map::new("attestations");
+pub const REPUTATIONS: Map<&Addr, Reputation> = Map::new("reputations");
+pub const REFERRAL_STATS: Map<&Addr, ReferralStats> = Map::new("referral_stats");
+// settled rewards, paid out on `ClaimReferralRewards`
+pub const REFERRAL_REWARDS: Map<&Addr, Vec<Coin>> = Map::new("referral_rewards");
+// refunds credited to backers, paid out on `ClaimRefund`
+pub const REFUNDS: Map<&Addr, Vec<Coin>> = Map::new("refunds");
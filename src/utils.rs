@@ -1,15 +1,37 @@
 use base64::{engine::general_purpose, Engine};
-use cosmwasm_std::{Addr, BlockInfo};
+use cosmwasm_std::{Addr, Binary, BlockInfo};
+use sha2::{Digest, Sha256};
 
 #[allow(unused_imports)]
 pub fn generate_id(id: Addr, block_info: BlockInfo) -> String {
     let account_id = id;
     let mut raw_id = account_id.to_owned().to_string();
-    raw_id.push_str("_");
+    raw_id.push('_');
     raw_id.push_str(&(block_info.time.to_string()));
     let u8_id = raw_id.as_bytes();
-    let vec_id: Vec<u8> = u8_id.iter().cloned().collect();
+    let vec_id: Vec<u8> = u8_id.to_vec();
     // let enc_str = base64::encode(vec_id);
-    let enc_str = general_purpose::STANDARD_NO_PAD.encode(vec_id);
-    return enc_str;
+    general_purpose::STANDARD_NO_PAD.encode(vec_id)
+}
+
+// promo codes are stored as sha256 digests so they can't be read from state
+pub fn hash_code(code: &str) -> Binary {
+    Binary::from(Sha256::digest(code.as_bytes()).to_vec())
+}
+
+// devices sign sha256(project_id || 0x00 || device_id || 0x00 || counter || payload_hash)
+pub fn telemetry_digest(
+    project_id: &str,
+    device_id: &str,
+    payload_hash: &[u8],
+    counter: u64,
+) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(project_id.as_bytes());
+    hasher.update([0]);
+    hasher.update(device_id.as_bytes());
+    hasher.update([0]);
+    hasher.update(counter.to_be_bytes());
+    hasher.update(payload_hash);
+    hasher.finalize().to_vec()
 }